@@ -0,0 +1,150 @@
+/*
+Tab completion for the line editor. The game hands over what is around the
+player before every prompt (see Game::completions()), and whatever was typed so
+far decides which of those are offered: verbs first, then directions after 'go',
+items in the room after 'grab', items in the bag after 'use' or 'drop' and
+anything in reach after 'on', 'open', 'lock' and the like. The words offered are
+the ones for the game's language.
+*/
+use rustyline::Context;
+use rustyline::Helper;
+use rustyline::completion::{Completer, Pair};
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use i18n;
+use parser;
+
+// every verb the player can type, in the order they're offered
+const VERBS: [&str; 19] = ["words.go", "words.grab", "words.drop", "words.use", "words.open", "words.close", "words.lock", "words.unlock", "words.light", "words.break", "words.look", "words.inventory", "words.map", "words.hint", "words.score", "words.again", "words.script", "words.unscript", "words.quit"];
+
+// the verbs in the game's language, without the abbreviations
+fn verbs() -> Vec<String> {
+    let mut verbs: Vec<String> = Vec::new();
+    for &list in VERBS.iter() {
+        for (i, word) in i18n::words(list).into_iter().enumerate() {
+            if i == 0 || word.chars().count() > 2 {
+                verbs.push(word.to_string());
+            }
+        }
+    }
+    verbs.push("?".to_string());
+    verbs
+}
+
+#[derive(Default)]
+pub struct Completions {
+    pub exits: Vec<&'static str>,
+    pub room_items: Vec<String>,
+    pub inventory: Vec<String>,
+}
+
+// where the command being typed starts, when there are several on the line
+fn command_start(line: &str) -> usize {
+    let lowercase_line = line.to_lowercase();
+    let mut separators = vec![", ".to_string(), ". ".to_string()];
+    separators.extend(i18n::all_words("words.then").iter().map(|then| format!(" {} ", then.to_lowercase())));
+    separators.iter()
+        .filter_map(|separator| lowercase_line.rfind(separator).map(|i| i + separator.len()))
+        .max()
+        .unwrap_or(0)
+}
+
+impl Completions {
+    // returns where the completed text starts in line, and what could go there
+    fn candidates(&self, line: &str) -> (usize, Vec<String>) {
+        let start = command_start(line);
+        let (offset, words) = self.command_candidates(line[start ..].trim_start());
+        let skipped_spaces = line[start ..].len() - line[start ..].trim_start().len();
+        (start + skipped_spaces + offset, words)
+    }
+
+    fn command_candidates(&self, line: &str) -> (usize, Vec<String>) {
+        let lowercase_line = line.to_lowercase();
+        let verb_end = match lowercase_line.find(' ') {
+            Some(i) => i,
+            // still typing the verb
+            None => return (0, starting_with(verbs().into_iter(), &lowercase_line)),
+        };
+        let verb = &lowercase_line[.. verb_end];
+        let rest_start = verb_end + 1;
+        let rest = &lowercase_line[rest_start ..];
+
+        match parser::verb(verb) {
+            Some("go") => {
+                if rest.contains(' ') {
+                    return (rest_start, Vec::new());
+                }
+                (rest_start, starting_with(self.exits.iter().map(|exit| exit.to_string()), rest))
+            },
+            Some("grab") => (rest_start, starting_with(self.room_items.iter().cloned(), rest)),
+            Some("drop") => (rest_start, starting_with(self.inventory.iter().cloned(), rest)),
+            Some("open") | Some("close") | Some("lock") | Some("unlock") | Some("light") | Some("break") => {
+                let in_reach = self.room_items.iter().chain(self.inventory.iter()).cloned();
+                (rest_start, starting_with(in_reach, rest))
+            },
+            Some("use") => {
+                match object_start(rest) {
+                    // use ITEM_NAME on OBJECT; the object can be anything in reach
+                    Some(object_start) => {
+                        let in_reach = self.room_items.iter().chain(self.inventory.iter()).cloned();
+                        (rest_start + object_start, starting_with(in_reach, &rest[object_start ..]))
+                    },
+                    // only things in the bag can be used
+                    None => {
+                        let with_on = self.inventory.iter().map(|item| format!("{} {}", item, i18n::words("words.on")[0]));
+                        (rest_start, starting_with(with_on, rest))
+                    },
+                }
+            },
+            _ => (rest_start, Vec::new()),
+        }
+    }
+}
+
+// where OBJECT starts in "ITEM_NAME on OBJECT", once the space after "on" is typed
+fn object_start(rest: &str) -> Option<usize> {
+    let mut end = 0;
+    let mut start = None;
+    for (i, word) in rest.split(' ').enumerate() {
+        end += word.len() + 1;
+        if i > 0 && end <= rest.len() && i18n::is_word(word, "words.on") {
+            start = Some(end);
+        }
+    }
+    start
+}
+
+fn starting_with<I: Iterator<Item = String>>(words: I, typed: &str) -> Vec<String> {
+    let mut words: Vec<String> = words.filter(|word| word.to_lowercase().starts_with(typed)).collect();
+    words.dedup();
+    words
+}
+
+// what the line editor talks to; it only ever completes
+#[derive(Default)]
+pub struct CommandHelper {
+    pub completions: Completions,
+}
+
+impl Completer for CommandHelper {
+    type Candidate = Pair;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let (start, words) = self.completions.candidates(&line[.. pos]);
+        let pairs = words.into_iter()
+            .map(|word| Pair { display: word.clone(), replacement: format!("{} ", word) })
+            .collect();
+        Ok((start, pairs))
+    }
+}
+
+impl Hinter for CommandHelper {
+    type Hint = String;
+}
+
+impl Highlighter for CommandHelper {}
+
+impl Validator for CommandHelper {}
+
+impl Helper for CommandHelper {}
@@ -0,0 +1,356 @@
+/*
+Everything the player sees and types goes through here instead of straight to
+stdout/stdin. That gives us one place to copy a session into a transcript file
+and one place to feed a saved list of commands back into the game. It's also
+where the markup in the text is turned into colours and lines are wrapped to
+fit the terminal (see markup.rs).
+*/
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::env;
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader, IsTerminal, Write};
+use std::path::PathBuf;
+use rustyline::{CompletionType, Config, Editor};
+use rustyline::error::ReadlineError;
+use rustyline::history::FileHistory;
+use terminal_size::{terminal_size, Width};
+use completion::{CommandHelper, Completions};
+use markup;
+use markup::{Style, Wrapper};
+
+// print!() replacement; also copies the text into the transcript, if any.
+// A lone string is written as it is, so markup like {item:cat} doesn't need
+// its braces doubled.
+macro_rules! say {
+    ($text:literal) => { ::console::write($text) };
+    ($($arg:tt)*) => { ::console::write(&format!($($arg)*)) };
+}
+
+// println!() replacement; also copies the text into the transcript, if any
+macro_rules! sayln {
+    () => { ::console::write("\n") };
+    ($text:literal) => { ::console::write(concat!($text, "\n")) };
+    ($($arg:tt)*) => { ::console::write(&format!("{}\n", format!($($arg)*))) };
+}
+
+struct Console {
+    transcript: Option<File>,
+    replay: VecDeque<String>,
+    // no prompts, no waiting for key presses; for when a script is driving
+    batch: bool,
+    // arrow keys, history, Ctrl-R search and tab completion when a person is at the keyboard
+    editor: Option<Editor<CommandHelper, FileHistory>>,
+    history_file: Option<PathBuf>,
+    // swallow everything written; for running handlers nobody should see
+    muted: bool,
+    // for screen readers: no colours and no line breaks in the middle of sentences
+    accessible: bool,
+    // the items marked up in what was written, while noted() is listening
+    noted: Option<Vec<String>>,
+    wrapper: Wrapper,
+}
+
+thread_local! {
+    static CONSOLE: RefCell<Console> = const { RefCell::new(Console {
+        transcript: None,
+        replay: VecDeque::new(),
+        batch: false,
+        editor: None,
+        history_file: None,
+        muted: false,
+        accessible: false,
+        noted: None,
+        wrapper: Wrapper::new(),
+    }) };
+}
+
+pub fn write(text: &str) {
+    if is_muted() {
+        return;
+    }
+    CONSOLE.with(|console| {
+        if let Some(ref mut noted) = console.borrow_mut().noted {
+            noted.extend(markup::items(text).into_iter().map(|item| item.to_string()));
+        }
+    });
+    let screen_text = markup::render(text, screen_style());
+    let screen_text = match terminal_size() {
+        Some((Width(width), _)) if !is_accessible() => CONSOLE.with(|console| console.borrow_mut().wrapper.wrap(&screen_text, width as usize)),
+        // not a terminal, so nothing to fit the text to; a screen reader fits it itself
+        _ => screen_text,
+    };
+    print!("{}", screen_text);
+    io::stdout().flush().unwrap(); // needed to ensure results of print!() are shown on stdout
+
+    CONSOLE.with(|console| {
+        let mut console = console.borrow_mut();
+        let failed = match console.transcript {
+            Some(ref mut file) => file.write_all(markup::render(text, Style::Capitals).as_bytes()).is_err(),
+            None => false,
+        };
+        // don't take the whole game down because the disk filled up
+        if failed {
+            console.transcript = None;
+            println!("\n(Could not write to the transcript file; recording stopped.)");
+        }
+    });
+}
+
+// like write(), but for text that's only there to ask the player for input.
+// Batch mode leaves it out of stdout; the transcript still gets it so that it
+// can be replayed later.
+pub fn prompt(text: &str) {
+    if is_batch() {
+        record(text);
+    }
+    else {
+        write(text);
+    }
+}
+
+// shows the prompt and reads one line of input, without the trailing newline.
+// Replayed commands are used first; once they run out, we fall back to the
+// keyboard. Returns None once there is nothing left to read.
+pub fn read_line(prompt_text: &str) -> Option<String> {
+    if let Some(line) = CONSOLE.with(|console| console.borrow_mut().replay.pop_front()) {
+        // echo the replayed command so the output reads like a real session
+        prompt(&format!("{}{}\n", prompt_text, line));
+        return Some(line);
+    }
+
+    let has_editor = CONSOLE.with(|console| console.borrow().editor.is_some());
+    let value = if has_editor {
+        edit_line(prompt_text)?
+    }
+    else {
+        prompt(prompt_text);
+
+        let mut value = String::new();
+        // read stdin up until \n into value; a read error ends the input just like EOF does
+        match io::stdin().read_line(&mut value) {
+            Ok(0) | Err(_) => {
+                // leave the cursor on a fresh line after the player hit Ctrl-D
+                prompt("\n");
+                return None;
+            },
+            Ok(_) => {},
+        }
+        let value = value.trim_end_matches(&['\r', '\n'][..]).to_string();
+
+        // the terminal echoed what was typed, but the transcript didn't see it
+        record(&format!("{}\n", value));
+        value
+    };
+
+    // the enter key took the cursor to a new line
+    CONSOLE.with(|console| console.borrow_mut().wrapper.new_line());
+    Some(value)
+}
+
+// read_line() for when the line editor is running. The editor draws the prompt
+// itself, so it only has to go into the transcript.
+fn edit_line(prompt_text: &str) -> Option<String> {
+    CONSOLE.with(|console| {
+        let mut console = console.borrow_mut();
+        let console = &mut *console;
+        let editor = console.editor.as_mut()?;
+
+        let value = match editor.readline(prompt_text) {
+            Ok(value) => value,
+            // Ctrl-D or Ctrl-C; either way the player is done
+            Err(ReadlineError::Eof) | Err(ReadlineError::Interrupted) => {
+                println!();
+                return None;
+            },
+            Err(e) => {
+                println!("\n(The line editor stopped working: {}. Falling back to plain input.)", e);
+                console.editor = None;
+                return Some(String::new());
+            },
+        };
+
+        if !value.trim().is_empty() {
+            let _ = editor.add_history_entry(value.as_str());
+            if let Some(ref history_file) = console.history_file {
+                // losing history isn't worth interrupting the game over
+                let _ = editor.append_history(history_file);
+            }
+        }
+
+        if let Some(ref mut file) = console.transcript {
+            let _ = file.write_all(format!("{}{}\n", prompt_text, value).as_bytes());
+        }
+        Some(value)
+    })
+}
+
+// waits for the player to hit enter. Replays don't record these and scripts
+// can't press keys, so neither of them wait.
+pub fn pause() {
+    if is_batch() {
+        return;
+    }
+    if is_replaying() {
+        sayln!();
+        return;
+    }
+    read_line("");
+}
+
+pub fn is_replaying() -> bool {
+    CONSOLE.with(|console| !console.borrow().replay.is_empty())
+}
+
+pub fn set_batch(batch: bool) {
+    CONSOLE.with(|console| console.borrow_mut().batch = batch);
+}
+
+pub fn is_batch() -> bool {
+    CONSOLE.with(|console| console.borrow().batch)
+}
+
+// runs f without anything it says reaching the screen or the transcript
+pub fn muted<T, F: FnOnce() -> T>(f: F) -> T {
+    let was_muted = is_muted();
+    CONSOLE.with(|console| console.borrow_mut().muted = true);
+    let result = f();
+    CONSOLE.with(|console| console.borrow_mut().muted = was_muted);
+    result
+}
+
+fn is_muted() -> bool {
+    CONSOLE.with(|console| console.borrow().muted)
+}
+
+// runs f, and returns the names of the items marked up in what it wrote
+pub fn noted<F: FnOnce()>(f: F) -> Vec<String> {
+    let was_noted = CONSOLE.with(|console| console.borrow_mut().noted.replace(Vec::new()));
+    f();
+    let noted = CONSOLE.with(|console| {
+        let mut console = console.borrow_mut();
+        let noted = console.noted.take().unwrap_or_default();
+        console.noted = was_noted;
+        noted
+    });
+    // each only once, even if the description mentions it twice
+    let mut items: Vec<String> = Vec::new();
+    for item in noted {
+        if !items.contains(&item) {
+            items.push(item);
+        }
+    }
+    items
+}
+
+pub fn set_accessible(accessible: bool) {
+    CONSOLE.with(|console| console.borrow_mut().accessible = accessible);
+}
+
+fn is_accessible() -> bool {
+    CONSOLE.with(|console| console.borrow().accessible)
+}
+
+// colours only make sense on a terminal, and some people would rather not
+// have them even there (https://no-color.org)
+fn screen_style() -> Style {
+    if is_accessible() {
+        Style::Plain
+    }
+    else if io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none() {
+        Style::Colour
+    }
+    else {
+        Style::Capitals
+    }
+}
+
+// whether somebody is sitting at a keyboard, as opposed to input being piped in
+pub fn is_interactive() -> bool {
+    io::stdin().is_terminal()
+}
+
+// what tab completion should offer at the next prompt
+pub fn set_completions(completions: Completions) {
+    CONSOLE.with(|console| {
+        if let Some(ref mut editor) = console.borrow_mut().editor {
+            if let Some(helper) = editor.helper_mut() {
+                helper.completions = completions;
+            }
+        }
+    });
+}
+
+// switches from plain stdin to the line editor. History from earlier sessions
+// is loaded from history_file, and every new line is appended to it.
+pub fn start_line_editor(history_file: Option<PathBuf>) {
+    // list all the candidates when there's more than one, like a shell does
+    let config = Config::builder()
+        .completion_type(CompletionType::List)
+        .build();
+    let mut editor = match Editor::with_config(config) {
+        Ok(editor) => editor,
+        // no editor; we can live with plain stdin
+        Err(_) => return,
+    };
+    editor.set_helper(Some(CommandHelper::default()));
+    if let Some(ref history_file) = history_file {
+        // there is no history file the first time around
+        let _ = editor.load_history(history_file);
+    }
+
+    CONSOLE.with(|console| {
+        let mut console = console.borrow_mut();
+        console.editor = Some(editor);
+        console.history_file = history_file;
+    });
+}
+
+pub fn start_transcript(path: &str) -> io::Result<()> {
+    let file = File::create(path)?;
+    CONSOLE.with(|console| console.borrow_mut().transcript = Some(file));
+    Ok(())
+}
+
+// returns false if there was no transcript to stop
+pub fn stop_transcript() -> bool {
+    CONSOLE.with(|console| console.borrow_mut().transcript.take().is_some())
+}
+
+// queues up the commands stored in a file. Either a plain list of commands (one
+// per line, '#' starts a comment) or a transcript, in which case only the lines
+// typed at the '> ' prompt are used. Returns how many commands were queued.
+pub fn load_replay(path: &str) -> io::Result<usize> {
+    let file = File::open(path)?;
+    let mut lines = Vec::new();
+    for line in BufReader::new(file).lines() {
+        lines.push(line?);
+    }
+
+    let is_transcript = lines.iter().any(|line| line.starts_with("> "));
+    let commands: Vec<String> = lines.iter()
+        .filter_map(|line| {
+            if is_transcript {
+                line.strip_prefix("> ").map(|command| command.trim().to_string())
+            }
+            else {
+                Some(line.trim().to_string())
+            }
+        })
+        .filter(|command| !command.is_empty() && !command.starts_with('#'))
+        .collect();
+
+    let count = commands.len();
+    CONSOLE.with(|console| console.borrow_mut().replay.extend(commands));
+    Ok(count)
+}
+
+fn record(text: &str) {
+    CONSOLE.with(|console| {
+        if let Some(ref mut file) = console.borrow_mut().transcript {
+            // a failed write will be reported by the next call to write()
+            let _ = file.write_all(markup::render(text, Style::Capitals).as_bytes());
+        }
+    });
+}
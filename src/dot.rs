@@ -0,0 +1,115 @@
+/*
+Writes the world out as a Graphviz graph, for looking at the layout of a level:
+
+    adventure --dot > world.dot && dot -Tpng world.dot -o world.png
+
+or from another program, through the library:
+
+    let world = adventure::levels::create_world()?;
+    let graph = adventure::dot::to_dot(&world.rooms, world.start_room, &world.flags);
+
+Every room becomes a node listing the items in it and every exit an arrow.
+Exits that are closed at the start of the game are dashed and labelled with the
+flag that opens them, or the door (as in "shack_door open"). Like the
+validator, this finds out what can_move looks at by calling it, since there's
+no other way to see into the closures.
+*/
+use Direction;
+use Flags;
+use Properties;
+use Room;
+use properties::changes;
+
+const DIRECTIONS: [Direction; 4] = [Direction::North, Direction::South, Direction::East, Direction::West];
+
+// the whole graph, ready to be fed to dot; flags are the ones a new game starts with
+pub fn to_dot(rooms: &[Room], start_room: &str, flags: &Flags) -> String {
+    let mut dot = String::from("digraph world {\n");
+    dot.push_str("    node [shape=box];\n");
+
+    for room in rooms {
+        let mut label = room.name.to_string();
+        if !room.items.is_empty() {
+            let items: Vec<&str> = room.items.iter().map(|item| item.name.as_str()).collect();
+            label.push_str(&format!("\n({})", items.join(", ")));
+        }
+        let start = if room.id == start_room { ", peripheries=2" } else { "" };
+        dot.push_str(&format!("    {} [label={}{}];\n", quote(room.id), quote(&label), start));
+    }
+
+    dot.push('\n');
+    for room in rooms {
+        for direction in &DIRECTIONS {
+            let to = match room.connections.get(*direction) {
+                Some(to) => to,
+                None => continue,
+            };
+            let attributes = match gate(room, *direction, flags) {
+                Some(condition) => format!("label={}, style=dashed", quote(&format!("{}\n{}", direction.name(), condition))),
+                None => format!("label={}", quote(direction.name())),
+            };
+            dot.push_str(&format!("    {} -> {} [{}];\n", quote(room.id), quote(to), attributes));
+        }
+    }
+
+    dot.push_str("}\n");
+    dot
+}
+
+// what it takes to go that way, or None if nothing ever stands in the way
+fn gate(room: &Room, direction: Direction, flags: &Flags) -> Option<String> {
+    // a copy that hasn't been used yet, so we can see what can_move asks about
+    let start = flags.fresh();
+    let open_at_start = (room.can_move)(&start, &direction);
+    let mut consulted: Vec<&'static str> = start.used().into_iter().collect();
+    consulted.sort();
+
+    // which of the flags it looked at would change its mind
+    let mut conditions = Vec::new();
+    for flag in consulted {
+        let value = flags.get_key(flag) == Some(&true);
+        let mut flipped = flags.fresh();
+        flipped.update_key(flag, !value);
+        if (room.can_move)(&flipped, &direction) != open_at_start {
+            conditions.push(if value { format!("not {}", flag) } else { flag.to_string() });
+        }
+    }
+
+    // and which of the items' properties, one at a time
+    let mut consulted_items: Vec<&'static str> = start.used_items().into_iter().collect();
+    consulted_items.sort();
+    for item in consulted_items {
+        let properties = match flags.properties.get(item) {
+            Some(&properties) => properties,
+            None => continue,
+        };
+        let turned_around = [
+            Properties { open: properties.open.map(|open| !open), ..properties },
+            Properties { locked: properties.locked.map(|locked| !locked), ..properties },
+            Properties { lit: properties.lit.map(|lit| !lit), ..properties },
+            Properties { broken: properties.broken.map(|broken| !broken), ..properties },
+            Properties { raised: properties.raised.map(|raised| !raised), ..properties },
+        ];
+        for other in turned_around.iter().filter(|&&other| other != properties) {
+            let mut flipped = flags.fresh();
+            flipped.update_properties(item, *other);
+            if (room.can_move)(&flipped, &direction) != open_at_start {
+                for change in changes(&properties, other) {
+                    conditions.push(format!("{} {}", item, change));
+                }
+            }
+        }
+    }
+
+    match (open_at_start, conditions.is_empty()) {
+        (true, true) => None,
+        (true, false) => Some(format!("until {}", conditions.join(" or "))),
+        (false, true) => Some("never opens".to_string()),
+        (false, false) => Some(format!("needs {}", conditions.join(" or "))),
+    }
+}
+
+// a DOT string, with quotes and backslashes escaped
+pub fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+}
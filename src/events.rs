@@ -0,0 +1,62 @@
+/*
+Things that happen in a game, for whoever wants to know about them without
+having to patch every closure in levels.rs: achievements, statistics, a
+debugging panel... An observer is handed every event as it happens, and can be
+added from outside the game too, through the library:
+
+    let mut game = adventure::Game::new(adventure::levels::create_world()?);
+    game.subscribe(Box::new(|event: &Event| {
+        if let Event::ItemTaken(id) = *event {
+            // ...
+        }
+    }));
+    game.play("grab lever, go north");
+
+Observers only get to look; they can't change the game. One that needs to keep
+count of something can hold on to a Cell or RefCell.
+*/
+use std::fmt;
+use Direction;
+pub use properties::Property;
+
+pub enum Event {
+    // the player walked from one room into another
+    RoomEntered { from: &'static str, to: &'static str },
+    // an item went from a room into the bag
+    ItemTaken(&'static str),
+    // an item went from the bag into a room
+    ItemDropped(&'static str),
+    // an item from the bag was used, on another item or on the room (None)
+    ItemUsed { item: &'static str, on: Option<&'static str> },
+    // a flag ended up with a different value after a command
+    FlagChanged { flag: &'static str, value: bool },
+    // an item's properties changed: a door was opened, a vase broken...
+    ItemChanged { item: &'static str, change: Property },
+    // a light was lit, put out or burnt out
+    LightChanged { item: &'static str, lit: bool },
+    // there was no way through in that direction, or it was shut
+    MoveBlocked { room: &'static str, direction: Direction },
+    // a line the parser couldn't make sense of, and why
+    CommandRejected { input: String, reason: String },
+}
+
+pub type Observer = Box<dyn Fn(&Event)>;
+
+// one line per event, for --events
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Event::RoomEntered { from, to } => write!(f, "room entered: {} (from {})", to, from),
+            Event::ItemTaken(item) => write!(f, "item taken: {}", item),
+            Event::ItemDropped(item) => write!(f, "item dropped: {}", item),
+            Event::ItemUsed { item, on: Some(on) } => write!(f, "item used: {} on {}", item, on),
+            Event::ItemUsed { item, on: None } => write!(f, "item used: {}", item),
+            Event::FlagChanged { flag, value } => write!(f, "flag changed: {} = {}", flag, value),
+            Event::ItemChanged { item, change } => write!(f, "item changed: {} {}", item, change),
+            Event::LightChanged { item, lit: true } => write!(f, "light lit: {}", item),
+            Event::LightChanged { item, lit: false } => write!(f, "light out: {}", item),
+            Event::MoveBlocked { room, direction } => write!(f, "move blocked: {} from {}", direction.name(), room),
+            Event::CommandRejected { ref input, ref reason } => write!(f, "command rejected: '{}' ({})", input, reason),
+        }
+    }
+}
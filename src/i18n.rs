@@ -0,0 +1,163 @@
+/*
+Everything the player reads or types, in their language. The text lives in
+lang/, one file per language (en.txt...), with one "id = text" line per message:
+
+    move.dead_end = It seems to be a dead end.
+    use.not_carried = You don't possess a {0}.
+
+{0}, {1}... are filled in by tr!(), in the order they're given:
+
+    sayln!("{}", tr!("use.not_carried", item_name));
+
+Lists of words (what the player can type, item aliases) are comma-separated;
+the first word in a list is the one the game uses itself, in the help and in
+walkthroughs. English is always there to fall back on, both for messages a
+language leaves out and for commands, so a walkthrough or a replay works
+whatever language the game is in.
+
+The files are built into the program, so a language can't be added without
+recompiling; LANGUAGES below lists them. Only the game itself is translated; the
+tools for level designers (--check, --dot, --events, wizard mode) stay English.
+*/
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::env;
+use std::fmt::Display;
+
+// language code and catalog; the first one is the fallback for all the others
+const LANGUAGES: [(&str, &str); 3] = [
+    ("en", include_str!("../lang/en.txt")),
+    ("fr", include_str!("../lang/fr.txt")),
+    ("de", include_str!("../lang/de.txt")),
+];
+
+// looks a message up by ID, with {0}, {1}... filled in from the arguments
+macro_rules! tr {
+    ($id:expr) => { ::i18n::text($id) };
+    ($id:expr, $($arg:expr),+) => { ::i18n::format($id, &[$(&$arg),+]) };
+}
+
+type Catalog = HashMap<&'static str, &'static str>;
+
+struct Language {
+    // index into LANGUAGES
+    current: usize,
+    // parsed the first time they're needed
+    catalogs: Vec<Option<Catalog>>,
+}
+
+thread_local! {
+    static LANGUAGE: RefCell<Language> = const { RefCell::new(Language { current: 0, catalogs: Vec::new() }) };
+}
+
+fn parse(source: &'static str) -> Catalog {
+    source.lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let equals = line.find('=')?;
+            Some((line[.. equals].trim(), line[equals + 1 ..].trim()))
+        })
+        .collect()
+}
+
+// the text for id in language, if that language has it
+fn lookup(language: usize, id: &str) -> Option<&'static str> {
+    LANGUAGE.with(|state| {
+        let mut state = state.borrow_mut();
+        if state.catalogs.is_empty() {
+            state.catalogs = LANGUAGES.iter().map(|_| None).collect();
+        }
+        state.catalogs[language]
+            .get_or_insert_with(|| parse(LANGUAGES[language].1))
+            .get(id)
+            .cloned()
+    })
+}
+
+fn current() -> usize {
+    LANGUAGE.with(|state| state.borrow().current)
+}
+
+// returns false if there is no such language
+pub fn set_language(code: &str) -> bool {
+    match LANGUAGES.iter().position(|&(language, _)| language == code) {
+        Some(language) => {
+            LANGUAGE.with(|state| state.borrow_mut().current = language);
+            true
+        },
+        None => false,
+    }
+}
+
+pub fn language_codes() -> Vec<&'static str> {
+    LANGUAGES.iter().map(|&(code, _)| code).collect()
+}
+
+// the language asked for in the environment: ADVENTURE_LANG, or failing that
+// the system's locale ("fr_FR.UTF-8" is French). None if neither says.
+pub fn language_from_env() -> Option<String> {
+    ["ADVENTURE_LANG", "LC_ALL", "LC_MESSAGES", "LANG"].iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .map(|value| value.split(['_', '.', '-']).next().unwrap_or("").to_lowercase())
+}
+
+// the message with this ID; the ID itself if no language has it, which is
+// easier to spot and fix than a crash
+pub fn text(id: &'static str) -> &'static str {
+    lookup(current(), id).or_else(|| lookup(0, id)).unwrap_or(id)
+}
+
+pub fn format(id: &'static str, args: &[&dyn Display]) -> String {
+    let mut text = text(id).to_string();
+    for (i, arg) in args.iter().enumerate() {
+        text = text.replace(&format!("{{{}}}", i), &arg.to_string());
+    }
+    text
+}
+
+// "a, b or c"; conjunction is the ID of the word that goes before the last one
+pub fn list(items: &[String], conjunction: &'static str) -> String {
+    match items.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} {} {}", rest.join(", "), text(conjunction), last),
+        _ => items.join(""),
+    }
+}
+
+// a comma-separated list of words
+pub fn words(id: &'static str) -> Vec<&'static str> {
+    split(text(id))
+}
+
+// words(), but English ones too. For what the player types: people who know
+// the English commands can keep using them, and walkthroughs work everywhere.
+pub fn all_words(id: &'static str) -> Vec<&'static str> {
+    let mut words = words(id);
+    if let Some(english) = lookup(0, id) {
+        words.extend(split(english).into_iter().filter(|word| !words.contains(word)).collect::<Vec<_>>());
+    }
+    words
+}
+
+fn split(list: &'static str) -> Vec<&'static str> {
+    list.split(',').map(|word| word.trim()).filter(|word| !word.is_empty()).collect()
+}
+
+// whether word is one of the words in the list id, in any case
+pub fn is_word(word: &str, id: &'static str) -> bool {
+    let word = word.to_lowercase();
+    all_words(id).iter().any(|&candidate| candidate.to_lowercase() == word)
+}
+
+// the IDs English has that language doesn't, for the validator
+pub fn missing(code: &str) -> Vec<&'static str> {
+    let language = match LANGUAGES.iter().position(|&(language, _)| language == code) {
+        Some(language) => language,
+        None => return Vec::new(),
+    };
+    let english = parse(LANGUAGES[0].1);
+    let mut missing: Vec<&'static str> = english.keys().filter(|id| lookup(language, id).is_none()).cloned().collect();
+    missing.sort();
+    missing
+}
@@ -1,10 +1,51 @@
+use std::collections::HashMap;
+use i18n;
 use Room;
 use Connection;
 use Flags;
 use Item;
+use Light;
+use Properties;
 use Direction;
+use Hint;
+use Recipe;
+use Target;
+use validate::Problem;
+use world::World;
 
-pub fn create_rooms() -> Vec<Room> {
+// how much the player can carry. The cat, the torch and the shovel don't all
+// fit at once, so something has to be left behind for a while.
+const CAPACITY: u32 = 10;
+
+pub fn create_world() -> Result<World, Vec<Problem>> {
+    World::new(create_rooms(), create_recipes(), create_hints(), create_flags(), "cell", CAPACITY)
+}
+
+// every flag the rooms and items below use has to be declared here
+fn create_flags() -> Flags {
+    Flags::new(map!{
+        // global flags
+        "isGameRunning" => true,
+        "gameWon" => false, // did the player find their way out?
+        // STARTING ROOM (cell) FLAGS
+        "pickedUpCat" => false, // the cat in the starting room
+        "initialSwitchPulled" => false, // switch in the starting room opening the way to the greenhouse
+        "pickedUpTorch" => false, // the torch lighting the starting room
+
+        // GREENHOUSE FLAGS
+        "pickedUpShovel" => false, // shovel in greenhouse
+        "pickedUpTwine" => false, // twine under the greenhouse table
+
+        // WELL FLAGS
+        "pickedUpKey" => false, // did player get key from bucket?
+        "bucketOnFloor" => false, // did the player pull on the rope AGAIN!?
+
+        // SHACK INTERIOR FLAGS
+        "isCatOnAltar" => false // has the cat been placed on altar?
+    })
+}
+
+fn create_rooms() -> Vec<Room> {
     /*
     The plan was to serialize these from a file, but is that possible with closures?
     */
@@ -12,71 +53,125 @@ pub fn create_rooms() -> Vec<Room> {
     A room has:
       - A description: What the player will see upon entering a room, or using
         the 'look' command.
-      - connections: room indices that this room can connect to, as a hashmap:
-          - north: i32,
-          - south: i32,
-          - east: i32,
-          - west: i32,
+      - id: what other rooms and the code refer to this room by.
+      - name: what the player sees the room called (on the map, for example).
+      - All the text is in lang/en.txt and its translations, under the IDs
+        given to tr!().
+      - connections: IDs of the rooms that this room can connect to:
+          - north: Option<&str>,
+          - south: Option<&str>,
+          - east: Option<&str>,
+          - west: Option<&str>,
+      - dark: whether the player needs a light (an item with a Light) to see
+        anything in it.
     */
     let rooms = vec!(
-        // ROOM 0 - Starting room
+        // Starting room
         Room {
-            connections: Connection::new(Some(1), None, None, None),
+            id: "cell",
+            name: tr!("room.cell"),
+            connections: Connection::new(Some("greenhouse"), None, None, None),
             items: vec![
                 Item {
-                    name: "cat".to_string(),
+                    id: "cat",
+                    name: tr!("item.cat").to_string(),
+                    aliases: aliases("item.cat.aliases"),
                     is_grabbable: true,
+                    weight: 4,
+                    properties: Properties::NONE,
+                    keys: Vec::new(),
                     on_grab: Box::new(|flags: &mut Flags| {
-                        println!("The cat purrs as you pick it up and fit it in your pocket.");
+                        sayln!("{}", tr!("cat.grab"));
                         flags.update_key("pickedUpCat", true);
+                        true
                     }),
-                    on_use: Box::new(|flags: &mut Flags, object_name: String, current_room: usize| -> bool {
-                        if current_room == 0 && object_name == "lever" {
-                            println!("The cat looks at the lever for a second before it begins to lick its paws.");
-                            false
-                        }
-                        else if current_room == 5 && object_name == "altar" {
-                            println!("You place the cat on the altar. It walks around for a second before settling down to lick its paws. You hear a clicking sound behind you. It seems like the altar had a pressure-sensitive plate on it, and that putting the cat on it revealed something else in the room.");
-                            flags.update_key("isCatOnAltar", true);
-                            true
-                        }
-                        else {
-                            println!("The cat isn't sure what to do with that.");
-                            false
+                    on_use: Some(Box::new(|flags: &mut Flags, target: &Target| -> bool {
+                        match *target {
+                            Target::Item(_) if target.is(flags, "lever") => {
+                                sayln!("{}", tr!("cat.use_on_lever"));
+                                false
+                            },
+                            Target::Item(_) if target.is(flags, "altar") => {
+                                sayln!("{}", tr!("cat.use_on_altar"));
+                                flags.update_key("isCatOnAltar", true);
+                                true
+                            },
+                            Target::Item(_) => {
+                                sayln!("{}", tr!("cat.use_on_other"));
+                                false
+                            },
+                            Target::Room(_) => {
+                                sayln!("{}", tr!("cat.use"));
+                                false
+                            },
                         }
-                    }),
+                    })),
+                    light: None,
                 },
                 Item {
-                    name: "lever".to_string(),
+                    id: "lever",
+                    name: tr!("item.lever").to_string(),
+                    aliases: aliases("item.lever.aliases"),
                     is_grabbable: false,
+                    weight: 0,
+                    properties: Properties::NONE,
+                    keys: Vec::new(),
                     on_grab: Box::new(|flags: &mut Flags| {
                         if flags.get_key("initialSwitchPulled") == Some(&false) {
-                            println!("You pull with all your might on the rusty lever as it slowly begins to fall. A loud crunching noise is heard from behind the walls as one of them shifts aside to reveal a doorway NORTH.");
+                            sayln!("{}", tr!("lever.grab"));
                             flags.update_key("initialSwitchPulled", true);
+                            true
                         }
                         else {
-                            println!("No matter how hard you try, the switch won't bduge. It seems to have arrived at it's final resting place.");
+                            sayln!("{}", tr!("lever.grab_again"));
+                            false
                         }
                     }),
-                    on_use: Box::new(|flags: &mut Flags, object_name: String, current_room: usize| -> bool {
-                        // don't need to implement this for items where is_grabbable == false
-                        false
+                    on_use: None,
+                    light: None,
+                },
+                Item {
+                    id: "torch",
+                    name: tr!("item.torch").to_string(),
+                    aliases: aliases("item.torch.aliases"),
+                    is_grabbable: true,
+                    weight: 2,
+                    properties: Properties { lit: Some(true), ..Properties::NONE },
+                    keys: Vec::new(),
+                    on_grab: Box::new(|flags: &mut Flags| {
+                        sayln!("{}", tr!("torch.grab"));
+                        flags.update_key("pickedUpTorch", true);
+                        true
                     }),
-                }
+                    on_use: None,
+                    // enough to get through the game a few times over, but not forever
+                    light: Some(Light { fuel: 150 }),
+                },
             ],
             get_description: Box::new(|flags: &Flags| {
+                // the torch lights the room for as long as it's still there
+                if flags.get_key("pickedUpTorch") == Some(&false) {
+                    say!("{} ", tr!("cell.intro"));
+                }
+                else {
+                    say!("{} ", tr!("cell.intro_no_torch"));
+                }
                 if flags.get_key("pickedUpCat") == Some(&false) {
-                    println!("You find yourself waking up in a small room lit by a single torch. A crooked table is in the corner, slightly rocking back and forth as if it took all of it's own strength to stay upright. On top of the table is a fat CAT, staring intently at you.");
+                    sayln!("{}", tr!("cell.description"));
                 }
                 else {
-                    println!("You find yourself waking up in a small room lit by a single torch. A crooked table is in the corner, slightly shaking as if it took all of it's own strength to stay upright. There is a recess in the table where the cat was laying (How long was it laying there for!?)");
+                    sayln!("{}", tr!("cell.description_no_cat"));
+                }
+
+                if flags.get_key("pickedUpTorch") == Some(&false) {
+                    sayln!("{}", tr!("cell.torch"));
                 }
 
                 if flags.get_key("initialSwitchPulled") == Some(&false) {
-                    println!("A rusted-covered LEVER is sticking out of the wall.");
+                    sayln!("{}", tr!("cell.lever"));
                 }
                 else {
-                    println!("A passageway has been revealed on the NORTH wall.");
+                    sayln!("{}", tr!("cell.passage"));
                 }
             }),
             can_move: Box::new(|flags: &Flags, direction: &Direction| -> bool {
@@ -85,240 +180,447 @@ pub fn create_rooms() -> Vec<Room> {
                     _ => true,
                 }
             }),
+            dark: false,
         },
-        // ROOM 1 - Greenhouse
+        // Greenhouse
         Room {
-            connections: Connection::new(Some(2), Some(0), None, None),
+            id: "greenhouse",
+            name: tr!("room.greenhouse"),
+            connections: Connection::new(Some("crossroads"), Some("cell"), None, None),
             items: vec![
                 Item {
-                    name: "shovel".to_string(),
+                    id: "shovel",
+                    name: tr!("item.shovel").to_string(),
+                    aliases: aliases("item.shovel.aliases"),
                     is_grabbable: true,
+                    weight: 5,
+                    properties: Properties::NONE,
+                    keys: Vec::new(),
                     on_grab: Box::new(|flags: &mut Flags| {
-                        println!("The shovel looks as if it has never been used before; the layer of dust that falls off as you pick it up shows that it has been sitting on that table for a long time. You slip the shovel in your pocket.");
+                        sayln!("{}", tr!("shovel.grab"));
                         flags.update_key("pickedUpShovel", true);
+                        true
                     }),
-                    on_use: Box::new(|flags: &mut Flags, object_name: String, current_room: usize| -> bool {
-                        // this sucks; checking if we are in the room before perfoming action
-                        if current_room == 1 && object_name == "glass door" {
-                            if flags.get_key("smashedDoor") == Some(&false) {
-                                println!("It takes a few swings before a couple of cracks appear in the glass. Wondering why such strong glass is needed for a greenhouse door, you continue to swing away until a loud crash and gust of fresh air announces the success of your swinging endeavours.");
-                                flags.update_key("smashedDoor", true);
+                    on_use: Some(Box::new(|flags: &mut Flags, target: &Target| -> bool {
+                        match *target {
+                            Target::Item(_) if target.is(flags, "glass_door") => {
+                                let door = glass_door(flags);
+                                if door.broken == Some(false) {
+                                    sayln!("{}", tr!("shovel.use_on_door"));
+                                    flags.update_properties("glass_door", Properties { broken: Some(true), ..door });
+                                }
+                                else {
+                                    sayln!("{}", tr!("shovel.use_on_door_again"));
+                                }
                                 false
-                            }
-                            else {
-                                println!("You seem to have already done a number on that poor door - maybe you should leave it alone?");
+                            },
+                            Target::Item(item) => {
+                                sayln!("{}", tr!("shovel.use_on_other", item.name));
                                 false
-                            }
-                        }
-                        else {
-                            println!("You aren't sure how to use the shovel with the {}", object_name);
-                            false
+                            },
+                            Target::Room(_) => {
+                                sayln!("{}", tr!("shovel.use"));
+                                false
+                            },
                         }
-                    }),
+                    })),
+                    light: None,
                 },
                 Item {
-                    name: "glass door".to_string(),
+                    id: "glass_door",
+                    name: tr!("item.glass_door").to_string(),
+                    aliases: aliases("item.glass_door.aliases"),
                     is_grabbable: false,
+                    weight: 0,
+                    properties: Properties { broken: Some(false), ..Properties::NONE },
+                    // what it takes to break it
+                    keys: vec!["shovel"],
+                    on_grab: Box::new(|_flags: &mut Flags| {
+                        sayln!("{}", tr!("glass_door.grab"));
+                        false
+                    }),
+                    on_use: None,
+                    light: None,
+                },
+                Item {
+                    id: "twine",
+                    name: tr!("item.twine").to_string(),
+                    aliases: aliases("item.twine.aliases"),
+                    is_grabbable: true,
+                    weight: 1,
+                    properties: Properties::NONE,
+                    keys: Vec::new(),
                     on_grab: Box::new(|flags: &mut Flags| {
-                        println!("You search the door for a handle or crevice but find nothing.  It's perfectly flat with nothing to grab onto.");
+                        sayln!("{}", tr!("twine.grab"));
+                        flags.update_key("pickedUpTwine", true);
+                        true
                     }),
-                    on_use: Box::new(|flags: &mut Flags, object_name: String, current_room: usize| -> bool {
-                        // don't need to implement this for items where is_grabbable == false
+                    on_use: Some(Box::new(|_flags: &mut Flags, _target: &Target| -> bool {
+                        sayln!("{}", tr!("twine.use"));
                         false
-                    }),
-                }
+                    })),
+                    light: None,
+                },
             ],
             get_description: Box::new(|flags: &Flags| {
-                print!("You have arrived in what appears to be a greenhouse, filled with strange, brightly-coloured plants and grasses you've never seen before. The scent of sulphur hangs in the air. ");
-                if flags.get_key("smashedDoor") == Some(&false) {
-                    println!("On the NORTH end of the greenhouse is a GLASS DOOR, tightly shut.")
+                say!("{} ", tr!("greenhouse.description"));
+                if glass_door(flags).broken == Some(false) {
+                    sayln!("{}", tr!("greenhouse.door"))
                 }
                 else {
-                    println!("On the NORTH end of the greenhouse is an an open door with pieces of glass sprinkling the ground around it.");
+                    sayln!("{}", tr!("greenhouse.door_smashed"));
                 }
 
                 if flags.get_key("pickedUpShovel") == Some(&false) {
-                    println!("There is a short yet long table along the side of the greenhouse. Many items are sitting on it, including a SHOVEL.");
+                    sayln!("{}", tr!("greenhouse.table"));
                 }
                 else {
-                    println!("There is a short yet long table along the side of the greenhouse. Many items are sitting on it.");
+                    sayln!("{}", tr!("greenhouse.table_empty"));
+                }
+
+                if flags.get_key("pickedUpTwine") == Some(&false) {
+                    sayln!("{}", tr!("greenhouse.twine"));
                 }
             }),
             can_move: Box::new(|flags: &Flags, direction: &Direction| -> bool {
                 match *direction {
-                    Direction::North => flags.get_key("smashedDoor") == Some(&true),
+                    Direction::North => glass_door(flags).broken == Some(true),
                     _ => true,
                 }
             }),
+            dark: false,
         },
-        // ROOM 2 - Crossroads
+        // Crossroads
         Room {
-            connections: Connection::new(None, Some(1), Some(4), Some(3)),
+            id: "crossroads",
+            name: tr!("room.crossroads"),
+            connections: Connection::new(None, Some("greenhouse"), Some("shack"), Some("well")),
             items: vec![], // no items in crossroads
-            get_description: Box::new(|flags: &Flags| {
-                println!("For as far as the eye can see, there is nothing but rolling green hills around. You have reached a sort of crossroads with two paths in front of you. To the EAST is a path leading towards a forest. To the WEST, the path continues along the rolling landscape.");
+            get_description: Box::new(|_flags: &Flags| {
+                sayln!("{}", tr!("crossroads.description"));
             }),
-            can_move: Box::new(|flags: &Flags, direction: &Direction| -> bool {
+            can_move: Box::new(|_flags: &Flags, _direction: &Direction| -> bool {
                 true
             }),
+            dark: false,
         },
-        // ROOM 3 - Westward Well
+        // Westward Well
         Room {
-            connections: Connection::new(None, None, Some(2), None),
+            id: "well",
+            name: tr!("room.well"),
+            connections: Connection::new(None, None, Some("crossroads"), None),
             items: vec![
                 Item {
-                    name: "rope".to_string(),
+                    id: "rope",
+                    name: tr!("item.rope").to_string(),
+                    aliases: aliases("item.rope.aliases"),
                     is_grabbable: false,
+                    weight: 0,
+                    // whether the bucket on the end of it is up out of the well
+                    properties: Properties { raised: Some(false), ..Properties::NONE },
+                    keys: Vec::new(),
                     on_grab: Box::new(|flags: &mut Flags| {
-                        if flags.get_key("isBuckedPulledUp") == Some(&false) {
-                            println!("You slowly pull up on the rope. Peering down the well, you see a bucket tied to the end. After a minute, you pull the bucket out of the well and set it on the stone wall.");
-                            flags.update_key("isBuckedPulledUp", true);
+                        let rope = bucket_rope(flags);
+                        if rope.raised == Some(false) {
+                            sayln!("{}", tr!("rope.grab"));
+                            flags.update_properties("rope", Properties { raised: Some(true), ..rope });
+                            true
                         }
                         else if flags.get_key("bucketOnFloor") == Some(&false) {
-                            println!("You pull on the limp rope, and the bucket falls on the grass. Great job!");
+                            sayln!("{}", tr!("rope.grab_again"));
                             flags.update_key("bucketOnFloor", true);
+                            true
                         }
                         else {
-                            println!("You pull on the limp rope. Nothing happens. You make yourself a little sad.");
+                            sayln!("{}", tr!("rope.grab_once_more"));
+                            false
                         }
                     }),
-                    on_use: Box::new(|flags: &mut Flags, object_name: String, current_room: usize| -> bool {
-                        false
-                    }),
+                    on_use: None,
+                    light: None,
                 },
                 Item {
-                    name: "key".to_string(),
+                    id: "key",
+                    name: tr!("item.key").to_string(),
+                    aliases: aliases("item.key.aliases"),
                     is_grabbable: true,
+                    weight: 1,
+                    properties: Properties::NONE,
+                    keys: Vec::new(),
                     on_grab: Box::new(|flags: &mut Flags| {
-                        if flags.get_key("isBuckedPulledUp") == Some(&false) {
-                            println!("You don't see a key.");
+                        // still at the bottom of the well
+                        if bucket_rope(flags).raised == Some(false) {
+                            sayln!("{}", tr!("key.not_seen"));
+                            false
                         }
                         else {
-                            println!("You pick up the key and examine it for a second. The key is small and silver with not a single scratch on it. It looks like the sort of key used for a child's diary. You slip it into your pocket.");
+                            sayln!("{}", tr!("key.grab"));
                             flags.update_key("pickedUpKey", true);
+                            true
                         }
                     }),
-                    on_use: Box::new(|flags: &mut Flags, object_name: String, current_room: usize| -> bool {
-                        // this sucks; checking if we are in the room before perfoming action
-                        if current_room == 4 && object_name == "door" {
-                                println!("You insert the tiny silver key into the shack door and turn...");
-                                println!("It worked! The door is unlocked.");
-                                flags.update_key("shackDoorUnlocked", true);
-                                true
-                        }
-                        else {
-                            println!("You aren't sure how to use the key with {}.", object_name);
-                            false
-                        }
-                    }),
+                    on_use: Some(Box::new(use_key)),
+                    light: None,
                 },
             ],
             get_description: Box::new(|flags: &Flags| {
-                print!("The winding path seems to stop in front of a lone, stone well. Half of the well's wall seems to be falling outward onto the grass surrounding it. ");
-                if flags.get_key("isBuckedPulledUp") == Some(&false) {
-                    println!("A single ROPE hangs from the top of the well.")
+                say!("{} ", tr!("well.description"));
+                if bucket_rope(flags).raised == Some(false) {
+                    sayln!("{}", tr!("well.rope"))
                 }
                 else if flags.get_key("pickedUpKey") == Some(&false) {
-                    println!("A bucket sits on the edge of the well. Laying on the bottom of the bucket is a KEY.");
+                    sayln!("{}", tr!("well.bucket"));
                 }
                 else {
-                    println!("An empty bucket sits on the edge of the well.");
+                    sayln!("{}", tr!("well.bucket_empty"));
                 }
             }),
-            can_move: Box::new(|flags: &Flags, direction: &Direction| -> bool {
+            can_move: Box::new(|_flags: &Flags, _direction: &Direction| -> bool {
                 true
             }),
+            dark: false,
         },
-        // ROOM 4 - Weird Shack, ext.
+        // Weird Shack, ext.
         Room {
-            connections: Connection::new(None, None, Some(5), Some(2)),
+            id: "shack",
+            name: tr!("room.shack"),
+            connections: Connection::new(None, None, Some("shack_inside"), Some("crossroads")),
             items: vec![
                 Item {
-                    name: "door".to_string(),
+                    id: "shack_door",
+                    name: tr!("item.shack_door").to_string(),
+                    aliases: aliases("item.shack_door.aliases"),
                     is_grabbable: false,
-                    on_grab: Box::new(|flags: &mut Flags| {
-                        if flags.get_key("shackDoorUnlocked") == Some(&false) {
-                            println!("You attempt to open the door, but it seems to be locked.");
-                        }
-                        else if flags.get_key("shackDoorOpen") == Some(&false) {
-                            println!("The door to the shack opens.");
-                            flags.update_key("shackDoorOpen", true);
-                        }
-                        else {
-                            println!("'Hey, whattya want from me!?' someone yells; it seems to come from the door itself.");
-                        }
-                    }),
-                    on_use: Box::new(|flags: &mut Flags, object_name: String, current_room: usize| -> bool {
+                    weight: 0,
+                    // an ordinary door with a lock; properties.rs does the opening and unlocking
+                    properties: Properties { open: Some(false), locked: Some(true), ..Properties::NONE },
+                    keys: vec!["key", "key_necklace"],
+                    on_grab: Box::new(|_flags: &mut Flags| {
+                        sayln!("{}", tr!("shack_door.yells"));
                         false
                     }),
+                    on_use: None,
+                    light: None,
                 },
             ],
             get_description: Box::new(|flags: &Flags| {
-                println!("You have arrived at a tiny building that you can only describe as 'weird'. At first glance, it looks like a wooden garden shed. After staring at it for a second, it seemed as if one side of the shed was slowly growing and shrinking by a few inches. The other side of the shed looked as if it was shivering.");
-                if flags.get_key("shackDoorUnlocked") == Some(&false) {
-                    println!("The shack has a shut door with a very tiny lock on it.");
+                sayln!("{}", tr!("shack.description"));
+                let door = shack_door(flags);
+                if door.locked == Some(true) {
+                    sayln!("{}", tr!("shack.locked"));
                 }
-                else if flags.get_key("shackDoorOpen") == Some(&true) {
-                    println!("To the EAST, the door to the shack is wide open.");
+                else if door.open == Some(true) {
+                    sayln!("{}", tr!("shack.open"));
+                }
+                else {
+                    sayln!("{}", tr!("shack.closed"));
                 }
             }),
             can_move: Box::new(|flags: &Flags, direction: &Direction| -> bool {
                 match *direction {
-                    Direction::East => flags.get_key("shackDoorUnlocked") == Some(&true),
+                    Direction::East => shack_door(flags).open == Some(true),
                     _ => true,
                 }
             }),
+            dark: false,
         },
-        // ROOM 5 - Weird Shack, int.
+        // Weird Shack, int.
         Room {
-            connections: Connection::new(None, None, None, Some(4)),
+            id: "shack_inside",
+            name: tr!("room.shack_inside"),
+            connections: Connection::new(None, None, None, Some("shack")),
             items: vec![
                 Item {
-                    name: "altar".to_string(),
+                    id: "altar",
+                    name: tr!("item.altar").to_string(),
+                    aliases: aliases("item.altar.aliases"),
                     is_grabbable: false,
-                    on_grab: Box::new(|flags: &mut Flags| {
-                        println!("The altar won't budge. Something seems to be holding it in place from below.");
-                    }),
-                    on_use: Box::new(|flags: &mut Flags, object_name: String, current_room: usize| -> bool {
+                    weight: 0,
+                    properties: Properties::NONE,
+                    keys: Vec::new(),
+                    on_grab: Box::new(|_flags: &mut Flags| {
+                        sayln!("{}", tr!("altar.grab"));
                         false
                     }),
+                    on_use: None,
+                    light: None,
                 },
                 Item {
-                    name: "head".to_string(),
+                    id: "head",
+                    name: tr!("item.head").to_string(),
+                    aliases: aliases("item.head.aliases"),
                     is_grabbable: false,
+                    weight: 0,
+                    properties: Properties::NONE,
+                    keys: Vec::new(),
                     on_grab: Box::new(|flags: &mut Flags| {
-                        use std::io;
-                        println!("You pulled at the device on your head with all your might and it pops off...");
-                        println!("Suddenly, the world around you changes. You are no longer in a strange small shack in the middle of a field. You are in a small apartment in the middle of a city. It seems like this whole experience was a virtual reality game that you may have gotten a little to immersed in.");
-                        println!("With this realization, you become depressed, eat a bunch of Halloween candy and go to sleep.");
-                        println!("THE END!");
-                        println!("Press a key to exit...");
-
-                        let mut value = String::new();
-
-                        // read stdin up until \n into value, show error if something goes wrong
-                        io::stdin().read_line(&mut value)
-                            .expect("Failed to read line!");
+                        sayln!("{}", tr!("head.grab"));
+                        sayln!("{}", tr!("head.ending"));
+                        sayln!("{}", tr!("head.ending_2"));
+                        sayln!("{}", tr!("head.the_end"));
 
+                        flags.update_key("gameWon", true);
                         flags.update_key("isGameRunning", false);
+                        true
                     }),
-                    on_use: Box::new(|flags: &mut Flags, object_name: String, current_room: usize| -> bool {
-                        println!("You've made it this far, clearly you've already been using your head. Keep {} away from it!", object_name);
-                        false
-                    }),
+                    on_use: None,
+                    light: None,
                 },
             ],
             get_description: Box::new(|flags: &Flags| {
-                println!("You are now inside the strange shack. Inside, there is nothing but a thin ALTAR in the centre of the back wall.");
+                sayln!("{}", tr!("shack_inside.description"));
                 if flags.get_key("isCatOnAltar") == Some(&true) {
-                    println!("On one of the walls is a mirror. Looking inside of the mirror, you see that there is some strange device on your HEAD.");
+                    sayln!("{}", tr!("shack_inside.mirror"));
                 }
             }),
-            can_move: Box::new(|flags: &Flags, direction: &Direction| -> bool {
+            can_move: Box::new(|_flags: &Flags, _direction: &Direction| -> bool {
                 true
             }),
+            dark: true, // the torch from the cell lights it up
         },
     );
 
     rooms
 }
+
+// an item's other names, from the language files
+fn aliases(id: &'static str) -> Vec<String> {
+    i18n::words(id).into_iter().map(|alias| alias.to_string()).collect()
+}
+
+// whether the greenhouse door has been smashed yet
+fn glass_door(flags: &Flags) -> Properties {
+    flags.get_properties("glass_door").cloned().unwrap_or(Properties::NONE)
+}
+
+// whether the bucket's been pulled up out of the well
+fn bucket_rope(flags: &Flags) -> Properties {
+    flags.get_properties("rope").cloned().unwrap_or(Properties::NONE)
+}
+
+// what's become of the shack door
+fn shack_door(flags: &Flags) -> Properties {
+    flags.get_properties("shack_door").cloned().unwrap_or(Properties::NONE)
+}
+
+// both the key and the key necklace fit the shack door, which the game handles
+// by itself (see properties.rs). This is for using them on anything else.
+fn use_key(_flags: &mut Flags, target: &Target) -> bool {
+    match *target {
+        Target::Item(item) => {
+            sayln!("{}", tr!("key.use_on_other", item.name));
+            false
+        },
+        Target::Room(_) => {
+            sayln!("{}", tr!("key.use"));
+            false
+        },
+    }
+}
+
+fn create_recipes() -> Vec<Recipe> {
+    vec![
+        Recipe {
+            ingredients: ("key", "twine"),
+            result: Item {
+                id: "key_necklace",
+                name: tr!("item.key_necklace").to_string(),
+                aliases: aliases("item.key_necklace.aliases"),
+                is_grabbable: true,
+                weight: 1,
+                properties: Properties::NONE,
+                keys: Vec::new(),
+                on_grab: Box::new(|_flags: &mut Flags| true),
+                on_use: Some(Box::new(use_key)),
+                light: None,
+            },
+            craft: Box::new(|_flags: &mut Flags| {
+                sayln!("{}", tr!("key_necklace.craft"));
+            }),
+        },
+    ]
+}
+
+// one hint per puzzle, in the order they get solved. The player gets the first
+// one that applies, so each only has to check that its own step isn't done yet.
+fn create_hints() -> Vec<Hint> {
+    vec![
+        Hint {
+            applies: Box::new(|flags: &Flags, _room: &'static str| flags.get_key("initialSwitchPulled") == Some(&false)),
+            clues: vec![
+                tr!("hint.lever.1"),
+                tr!("hint.lever.2"),
+                tr!("hint.lever.3"),
+            ],
+        },
+        Hint {
+            applies: Box::new(|flags: &Flags, room: &'static str| glass_door(flags).broken == Some(false) && room == "cell"),
+            clues: vec![
+                tr!("hint.leave_cell.1"),
+                tr!("hint.leave_cell.2"),
+            ],
+        },
+        Hint {
+            applies: Box::new(|flags: &Flags, _room: &'static str| glass_door(flags).broken == Some(false)),
+            clues: vec![
+                tr!("hint.glass_door.1"),
+                tr!("hint.glass_door.2"),
+                tr!("hint.glass_door.3"),
+            ],
+        },
+        Hint {
+            applies: Box::new(|flags: &Flags, _room: &'static str| shack_door(flags).locked == Some(true) && bucket_rope(flags).raised == Some(false)),
+            clues: vec![
+                tr!("hint.well.1"),
+                tr!("hint.well.2"),
+                tr!("hint.well.3"),
+            ],
+        },
+        Hint {
+            applies: Box::new(|flags: &Flags, _room: &'static str| shack_door(flags).locked == Some(true)),
+            clues: vec![
+                tr!("hint.lock.1"),
+                tr!("hint.lock.2"),
+                tr!("hint.lock.3"),
+            ],
+        },
+        Hint {
+            applies: Box::new(|flags: &Flags, _room: &'static str| shack_door(flags).open == Some(false)),
+            clues: vec![
+                tr!("hint.open_door.1"),
+                tr!("hint.open_door.2"),
+            ],
+        },
+        Hint {
+            applies: Box::new(|flags: &Flags, room: &'static str| flags.get_key("isCatOnAltar") == Some(&false) && room != "shack_inside"),
+            clues: vec![
+                tr!("hint.enter_shack.1"),
+                tr!("hint.enter_shack.2"),
+            ],
+        },
+        Hint {
+            applies: Box::new(|flags: &Flags, room: &'static str| flags.get_key("pickedUpTorch") == Some(&false) && room == "shack_inside"),
+            clues: vec![
+                tr!("hint.torch.1"),
+                tr!("hint.torch.2"),
+                tr!("hint.torch.3"),
+            ],
+        },
+        Hint {
+            applies: Box::new(|flags: &Flags, _room: &'static str| flags.get_key("isCatOnAltar") == Some(&false)),
+            clues: vec![
+                tr!("hint.altar.1"),
+                tr!("hint.altar.2"),
+                tr!("hint.altar.3"),
+            ],
+        },
+        Hint {
+            applies: Box::new(|_flags: &Flags, _room: &'static str| true),
+            clues: vec![
+                tr!("hint.head.1"),
+                tr!("hint.head.2"),
+                tr!("hint.head.3"),
+            ],
+        },
+    ]
+}
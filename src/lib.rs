@@ -0,0 +1,1378 @@
+/*
+Problems encountered:
+
+- Originally, I tried to handle this in a more OO fashion. I had 'Room' structs
+  that would contain 'Connection' objects that contained north:
+  Option<Box<Room>>, etc... The main 'current_room' variable binding was
+  pointing to an element of a Vec<Box<Room>>. Howeveer, I had issues with
+  structs that could contain fields that point to itself. I also had trouble
+  pulling objects to mutate out of a vector.
+*/
+extern crate rustyline;
+extern crate serde;
+extern crate terminal_size;
+#[cfg(test)]
+extern crate serde_json;
+
+// defined before the modules so that levels.rs can use it too
+// source: http://stackoverflow.com/questions/27582739/how-do-i-create-a-hashmap-literal
+macro_rules! map(
+    { $($key:expr => $value:expr),+ } => {
+        {
+            let mut m = HashMap::new();
+            $(
+                m.insert($key, $value);
+            )+
+                m
+        }
+    };
+);
+
+#[macro_use]
+mod console;
+#[macro_use]
+mod i18n;
+mod completion;
+pub mod dot;
+pub mod events;
+pub mod levels;
+mod map;
+mod markup;
+mod nouns;
+mod parser;
+mod properties;
+mod puzzles;
+mod solver;
+mod state;
+mod validate;
+mod wizard;
+pub mod world;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::env;
+use std::path::PathBuf;
+use std::process;
+use events::{Event, Observer};
+use nouns::Resolved;
+use properties::{Action, Property};
+use serde::{Serialize, Serializer};
+use state::GameState;
+use world::World;
+
+enum Command {
+    Walk(Direction),
+    Grab(String),
+    GrabAll(Vec<String>), // everything but these
+    Drop(String),
+    Use(String, Option<String>), // the item, and what it's used on if anything
+    Handle(Action, String), // open, lock, light... something
+    Look,
+    Map,
+    Hint,
+    Score,
+    Inventory,
+    Quit,
+    Help,
+    Script(String),
+    Unscript,
+    Wizard(wizard::Spell), // only with --wizard
+}
+
+impl Command {
+    // whether doing this lets a turn go by (and lights burn down). Looking
+    // things up about the game itself doesn't.
+    fn takes_time(&self) -> bool {
+        !matches!(*self, Command::Map | Command::Hint | Command::Score | Command::Quit | Command::Help | Command::Script(_) | Command::Unscript | Command::Wizard(_))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Direction {
+    North,
+    South,
+    East,
+    West,
+}
+
+impl Direction {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Direction::North => "north",
+            Direction::South => "south",
+            Direction::East => "east",
+            Direction::West => "west",
+        }
+    }
+
+    // what the player types for it, in their language
+    pub fn local_name(&self) -> &'static str {
+        let words = match *self {
+            Direction::North => i18n::words("words.north"),
+            Direction::South => i18n::words("words.south"),
+            Direction::East => i18n::words("words.east"),
+            Direction::West => i18n::words("words.west"),
+        };
+        words.first().cloned().unwrap_or(self.name())
+    }
+}
+
+#[derive(Clone)]
+pub struct Flags {
+    flag_map: BTreeMap<&'static str, bool>,
+    // item ID -> what state it's in (open, locked...). Kept in here rather than
+    // in the items so that the handlers get to see it.
+    properties: BTreeMap<&'static str, Properties>,
+    // every flag that was looked at or changed, declared or not; the validator
+    // uses this to find typos in flag names. Not part of the game state, so
+    // it's left out when flags are compared, hashed or written out.
+    used: RefCell<HashSet<&'static str>>,
+    // same for the items whose properties were looked at or changed
+    used_items: RefCell<HashSet<&'static str>>,
+    // and for the items that use targets were checked against; see Target::is
+    used_targets: RefCell<HashSet<&'static str>>,
+}
+
+impl PartialEq for Flags {
+    fn eq(&self, other: &Flags) -> bool {
+        self.flag_map == other.flag_map && self.properties == other.properties
+    }
+}
+
+impl Eq for Flags {}
+
+impl Hash for Flags {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.flag_map.hash(state);
+        self.properties.hash(state);
+    }
+}
+
+// only the flags themselves; GameState writes the properties out next to them
+impl Serialize for Flags {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.flag_map.serialize(serializer)
+    }
+}
+
+impl Flags {
+    pub fn new<I: IntoIterator<Item = (&'static str, bool)>>(flag_map: I) -> Self {
+        Flags { flag_map: flag_map.into_iter().collect(), properties: BTreeMap::new(), used: RefCell::new(HashSet::new()), used_items: RefCell::new(HashSet::new()), used_targets: RefCell::new(HashSet::new()) }
+    }
+
+    // a copy of the flags and properties with nothing marked as used yet
+    pub fn fresh(&self) -> Self {
+        Flags { flag_map: self.flag_map.clone(), properties: self.properties.clone(), used: RefCell::new(HashSet::new()), used_items: RefCell::new(HashSet::new()), used_targets: RefCell::new(HashSet::new()) }
+    }
+
+    // flags the game loop itself looks at, so levels don't have to
+    pub fn is_engine_flag(key_name: &str) -> bool {
+        key_name == "isGameRunning" || key_name == "gameWon"
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        self.flag_map.keys().cloned().collect()
+    }
+
+    pub fn used(&self) -> HashSet<&'static str> {
+        self.used.borrow().clone()
+    }
+
+    pub fn get_key(&self, key_name: &'static str) -> Option<&bool> {
+        self.used.borrow_mut().insert(key_name);
+        self.flag_map.get(key_name)
+    }
+
+    pub fn update_key(&mut self, key_name: &'static str, new_bool: bool) {
+        self.used.borrow_mut().insert(key_name);
+        if let Some(mut_key) = self.flag_map.get_mut(&key_name) {
+            *mut_key = new_bool;
+        }
+    }
+
+    pub fn used_items(&self) -> HashSet<&'static str> {
+        self.used_items.borrow().clone()
+    }
+
+    // None for items that don't exist
+    pub fn get_properties(&self, item_id: &'static str) -> Option<&Properties> {
+        self.used_items.borrow_mut().insert(item_id);
+        self.properties.get(item_id)
+    }
+
+    pub fn used_targets(&self) -> HashSet<&'static str> {
+        self.used_targets.borrow().clone()
+    }
+
+    pub fn update_properties(&mut self, item_id: &'static str, properties: Properties) {
+        self.used_items.borrow_mut().insert(item_id);
+        if let Some(mut_properties) = self.properties.get_mut(&item_id) {
+            *mut_properties = properties;
+        }
+    }
+}
+
+// either the ID of the next room or nothing
+pub struct Connection {
+    north: Option<&'static str>,
+    south: Option<&'static str>,
+    east: Option<&'static str>,
+    west: Option<&'static str>,
+}
+
+// returns false if nothing came of it; a grabbable item then stays where it is
+type GrabHandler = Box<dyn Fn(&mut Flags) -> bool>;
+// returns true if the item was used up
+type UseHandler = Box<dyn Fn(&mut Flags, &Target) -> bool>;
+type CraftHandler = Box<dyn Fn(&mut Flags)>;
+type DescriptionHandler = Box<dyn Fn(&Flags)>;
+type MoveHandler = Box<dyn Fn(&Flags, &Direction) -> bool>;
+// gets the ID of the room the player is in
+type HintCondition = Box<dyn Fn(&Flags, &'static str) -> bool>;
+
+pub struct Item {
+    id: &'static str, // what the code refers to it by; never shown to the player
+    name: String,
+    aliases: Vec<String>, // other names the player might call it by
+    is_grabbable: bool,
+    weight: u32, // how much of the bag it takes up; see World::capacity
+    properties: Properties, // as they are when the game starts
+    keys: Vec<&'static str>, // IDs of the items that lock and unlock it, if it has a lock, or that it takes to break it
+    on_grab: GrabHandler,
+    on_use: Option<UseHandler>, // None for things that can't be used at all
+    light: Option<Light>, // None for things that don't give any light
+}
+
+// the state an item is in, which the verbs in properties.rs change. None for
+// what doesn't apply to it: a key can't be opened, a door can't be lit.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub struct Properties {
+    open: Option<bool>,
+    locked: Option<bool>, // only opens once it's unlocked
+    lit: Option<bool>, // for lights, whether they're burning
+    broken: Option<bool>,
+    raised: Option<bool>, // for things that get pulled up and let down, like a bucket on a rope
+    count: Option<u32>, // how many there are, for things that come in piles; using one up takes one away
+}
+
+impl Properties {
+    pub const NONE: Properties = Properties { open: None, locked: None, lit: None, broken: None, raised: None, count: None };
+}
+
+// something that lights up dark rooms, like a torch. Using it lights it or puts
+// it out (its lit property says which it starts as); while it's lit it burns a
+// turn's worth of fuel every turn.
+pub struct Light {
+    fuel: u32, // how many turns it can burn for
+}
+
+// what an item is being used on
+pub enum Target<'a> {
+    // something in the room or in the bag
+    Item(&'a Item),
+    // nothing in particular ("use shovel"); the ID of the room the player is standing in
+    Room(&'static str),
+}
+
+impl<'a> Target<'a> {
+    // whether this is the item with the given ID. Handlers should ask this
+    // rather than compare IDs themselves, so that the validator gets to see
+    // which IDs they ask about and can catch typos.
+    pub fn is(&self, flags: &Flags, item_id: &'static str) -> bool {
+        flags.used_targets.borrow_mut().insert(item_id);
+        match *self {
+            Target::Item(item) => item.id == item_id,
+            Target::Room(_) => false,
+        }
+    }
+}
+
+// two items in the bag that can be combined into a new one
+pub struct Recipe {
+    ingredients: (&'static str, &'static str), // item IDs, in either order
+    result: Item, // what they make
+    craft: CraftHandler, // prints what happened
+}
+
+// help with one step of the puzzle chain
+pub struct Hint {
+    applies: HintCondition, // whether this is the step the player is stuck on
+    clues: Vec<&'static str>, // from vague to spelling it out
+}
+
+pub struct Room {
+    id: &'static str,
+    name: &'static str, // what the player sees it called, on the map for example
+    connections: Connection,
+    items: Vec<Item>,
+    get_description: DescriptionHandler,
+    can_move: MoveHandler,
+    dark: bool, // without a light, its description and items can't be seen
+}
+
+impl Connection {
+    pub fn new(north: Option<&'static str>, south: Option<&'static str>, east: Option<&'static str>, west: Option<&'static str>) -> Connection {
+        Connection { north, south, east, west }
+    }
+
+    pub fn get(&self, direction: Direction) -> Option<&'static str> {
+        match direction {
+            Direction::North => self.north,
+            Direction::South => self.south,
+            Direction::East => self.east,
+            Direction::West => self.west,
+        }
+    }
+
+    // the directions that lead somewhere, and where they lead
+    pub fn exits(&self) -> Vec<(Direction, &'static str)> {
+        [Direction::North, Direction::South, Direction::East, Direction::West].iter()
+            .filter_map(|&direction| self.get(direction).map(|room| (direction, room)))
+            .collect()
+    }
+}
+
+// a game being played: the world, and how far the player has got in it
+pub struct Game {
+    world: World,
+    state: GameState,
+    last_input: Option<String>, // for 'again'
+    observers: Vec<Observer>, // see events.rs
+    wizard: bool, // whether the commands in wizard.rs can be used
+    accessible: bool, // for screen readers; see describe_room()
+}
+
+impl Game {
+    pub fn new(world: World) -> Game {
+        Game {
+            state: GameState::new(&world),
+            world,
+            last_input: None,
+            observers: Vec::new(),
+            wizard: false,
+            accessible: false,
+        }
+    }
+
+    pub fn subscribe(&mut self, observer: Observer) {
+        self.observers.push(observer);
+    }
+
+    // plays a line as if the player had typed it, without asking for anything
+    // that's missing. Returns false if any of it didn't work out.
+    pub fn play(&mut self, line: &str) -> bool {
+        let mut commands = VecDeque::new();
+        for raw_command in parser::split_commands(line) {
+            match self.parse(&raw_command) {
+                Ok(command) => commands.push_back(command),
+                Err(error) => {
+                    sayln!("{}", error);
+                    self.emit(Event::CommandRejected { input: raw_command, reason: error.to_string() });
+                    return false;
+                },
+            }
+        }
+        self.run_commands(commands)
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.state.is_running()
+    }
+
+    pub fn is_won(&self) -> bool {
+        self.state.is_won()
+    }
+
+    // runs the commands from one line until one doesn't work out, which
+    // skips the rest; returns false if that happened
+    fn run_commands(&mut self, mut commands: VecDeque<Command>) -> bool {
+        while let Some(command) = commands.pop_front() {
+            let succeeded = self.process_command(command);
+            if !self.state.is_running() {
+                return succeeded;
+            }
+            if !succeeded {
+                if !commands.is_empty() {
+                    sayln!("{}", tr!("line.skipped"));
+                }
+                return false;
+            }
+        }
+        true
+    }
+
+    fn emit(&self, event: Event) {
+        for observer in &self.observers {
+            observer(&event);
+        }
+    }
+
+    // reads a line of input, which can hold several commands ("go north, grab shovel")
+    fn get_commands(&mut self) -> VecDeque<Command> {
+        console::set_completions(self.completions());
+        console::prompt(&format!("\n{}\n", tr!("prompt.command")));
+
+        // keep the raw input around; file names shouldn't be lowercased.
+        // Running out of input is the same as quitting.
+        let raw_value = match console::read_line("> ") {
+            Some(value) => value,
+            None => return VecDeque::from(vec![Command::Quit]),
+        };
+
+        // 'again' repeats whatever was entered last
+        let raw_value = if i18n::is_word(raw_value.trim(), "words.again") {
+            match self.last_input {
+                Some(ref last_input) => last_input.clone(),
+                None => {
+                    sayln!("{}", tr!("again.nothing"));
+                    return VecDeque::new();
+                },
+            }
+        }
+        else if raw_value.trim().is_empty() {
+            raw_value
+        }
+        else {
+            self.last_input = Some(raw_value.clone());
+            raw_value
+        };
+
+        // check the whole line before doing any of it; a half-understood line
+        // shouldn't leave the player halfway through what they meant to do
+        let mut commands = VecDeque::new();
+        for raw_command in parser::split_commands(&raw_value) {
+            match self.parse_or_ask(raw_command) {
+                Some(command) => commands.push_back(command),
+                None => return VecDeque::new(),
+            }
+        }
+        commands
+    }
+
+    // parses a command. If a piece of it is missing ("grab"), asks the player
+    // for it and tries again with their answer.
+    fn parse_or_ask(&self, mut raw_command: String) -> Option<Command> {
+        loop {
+            let error = match self.parse(&raw_command) {
+                Ok(command) => return Some(command),
+                Err(error) => error,
+            };
+            sayln!("{}", error);
+
+            // the question was already asked by printing the error
+            if !error.is_question() {
+                self.emit(Event::CommandRejected { input: raw_command, reason: error.to_string() });
+                return None;
+            }
+            let answer = console::read_line("> ")?;
+            let answer = answer.trim();
+            if answer.is_empty() {
+                return None;
+            }
+            // players often answer with a whole new command instead
+            raw_command = if answer.split_whitespace().next().is_some_and(parser::is_verb) {
+                answer.to_string()
+            }
+            else {
+                error.complete(&raw_command, answer)?
+            };
+        }
+    }
+
+    fn parse(&self, raw_command: &str) -> Result<Command, parser::ParseError> {
+        if self.wizard {
+            if let Some(result) = wizard::parse(raw_command) {
+                return result;
+            }
+        }
+        parser::parse(raw_command)
+    }
+
+    // what tab completion can offer from where the player is standing
+    fn completions(&self) -> completion::Completions {
+        let room = self.world.room(self.state.current_room);
+
+        completion::Completions {
+            exits: room.connections.exits().iter().map(|exit| exit.0.local_name()).collect(),
+            room_items: self.room_items().iter().map(|i| i.name.clone()).collect(),
+            inventory: self.bag().iter().map(|i| i.name.clone()).collect(),
+        }
+    }
+
+    // the items lying in the room the player is in, as far as they can see
+    fn room_items(&self) -> Vec<&Item> {
+        if !self.is_lit(self.state.current_room) {
+            return Vec::new();
+        }
+        self.state.items_in(self.state.current_room).iter().map(|id| self.world.item(id)).collect()
+    }
+
+    // whether there's enough light in a room to see by. The player's own light
+    // counts too, as they're about to walk in with it.
+    fn is_lit(&self, room_id: &'static str) -> bool {
+        !self.world.room(room_id).dark ||
+            self.state.inventory.iter().chain(self.state.items_in(room_id)).any(|&id| self.state.is_burning(id))
+    }
+
+    // uses up a turn's worth of fuel in every light that's burning
+    fn burn_lights(&mut self) {
+        let room = self.state.current_room;
+        let could_see = self.is_lit(room);
+        // only lights with fuel burn down; the others burn forever. Lights
+        // the player isn't carrying and can't see don't burn down either, or
+        // one left somewhere could go out without them ever knowing
+        let burning: Vec<&'static str> = self.state.fuel.keys().cloned()
+            .filter(|&id| self.state.is_burning(id))
+            .filter(|id| self.state.inventory.contains(id) || self.state.items_in(room).contains(id))
+            .collect();
+        for id in burning {
+            let fuel = self.state.fuel.get(id).cloned().unwrap_or(0).saturating_sub(1);
+            self.state.fuel.insert(id, fuel);
+            if fuel == 0 {
+                self.state.set_lit(id, false);
+                sayln!("{}", tr!("light.burnt_out", self.world.item(id).name));
+            }
+            else if fuel == LOW_FUEL {
+                sayln!("{}", tr!("light.low", self.world.item(id).name));
+            }
+        }
+        if could_see && !self.is_lit(room) {
+            sayln!("{}", tr!("dark.fallen"));
+        }
+    }
+
+    // using a light with nothing to use it on lights it, or puts it out
+    fn toggle_light(&mut self, id: &'static str) -> bool {
+        let name = &self.world.item(id).name;
+        if self.state.is_burning(id) {
+            self.state.set_lit(id, false);
+            sayln!("{}", tr!("light.put_out", name));
+        }
+        else if !self.light(id) {
+            return false;
+        }
+        self.emit(Event::ItemUsed { item: id, on: None });
+
+        // the room looks different now
+        if self.world.room(self.state.current_room).dark {
+            self.look();
+        }
+        true
+    }
+
+    // lights something that isn't burning yet, if there's anything left to burn
+    fn light(&mut self, id: &'static str) -> bool {
+        let name = &self.world.item(id).name;
+        if self.state.fuel.get(id) == Some(&0) {
+            sayln!("{}", tr!("light.no_fuel", name));
+            return false;
+        }
+        self.state.set_lit(id, true);
+        sayln!("{}", tr!("light.lit", name));
+        true
+    }
+
+    // the items the player is carrying
+    fn bag(&self) -> Vec<&Item> {
+        self.state.inventory.iter().map(|id| self.world.item(id)).collect()
+    }
+
+    // how much of the bag is taken up
+    fn load(&self) -> u32 {
+        self.bag().iter().map(|item| item.weight).sum()
+    }
+
+    // whether an item would still fit in the bag on top of what's in it
+    fn has_room_for(&self, item: &Item) -> bool {
+        self.load() + item.weight <= self.world.capacity
+    }
+
+    // returns false if the command didn't work out, so that whatever else was
+    // typed on the same line can be skipped
+    fn process_command(&mut self, command: Command) -> bool {
+        // the closures in levels.rs change flags all over the place; rather than
+        // have each of them say so, compare before and after
+        let before = self.state.flags.fresh();
+        let takes_time = command.takes_time();
+        let succeeded = self.run_command(command);
+        if takes_time && self.state.is_running() {
+            self.burn_lights();
+        }
+        for (&flag, &value) in &self.state.flags.flag_map {
+            if before.flag_map.get(flag) != Some(&value) {
+                self.emit(Event::FlagChanged { flag, value });
+            }
+        }
+        // the same goes for the items, whether it was a verb or a handler that changed them
+        for (&item, after) in &self.state.flags.properties {
+            for change in properties::changes(&before.properties[item], after) {
+                match change {
+                    Property::Lit(lit) => self.emit(Event::LightChanged { item, lit }),
+                    change => self.emit(Event::ItemChanged { item, change }),
+                }
+            }
+        }
+        succeeded
+    }
+
+    fn run_command(&mut self, command: Command) -> bool {
+        match command {
+            Command::Walk(direction) => {
+                let previous_room = self.state.current_room;
+                let next_room = self.world.room(previous_room).connections.get(direction);
+                self.state.current_room = self.change_room(next_room, &direction);
+                self.state.visited.insert(self.state.current_room);
+                if self.state.current_room == previous_room {
+                    return false;
+                }
+                self.emit(Event::RoomEntered { from: previous_room, to: self.state.current_room });
+                true
+            },
+            Command::Grab(item_name) => {
+                match self.pronoun(item_name) {
+                    Some(item_name) => self.pick_up_item(&item_name),
+                    None => false,
+                }
+            },
+            Command::GrabAll(exceptions) => {
+                let exceptions: Option<Vec<String>> = exceptions.into_iter().map(|name| self.pronoun(name)).collect();
+                match exceptions {
+                    Some(exceptions) => self.pick_up_all_items(&exceptions),
+                    None => false,
+                }
+            },
+            Command::Drop(item_name) => {
+                match self.pronoun(item_name) {
+                    Some(item_name) => self.drop_item(&item_name),
+                    None => false,
+                }
+            },
+            Command::Handle(action, item_name) => {
+                match self.pronoun(item_name) {
+                    Some(item_name) => self.handle(action, &item_name),
+                    None => false,
+                }
+            },
+            Command::Use(item_name, object_name) => {
+                let object_name = match object_name {
+                    Some(object_name) => match self.pronoun(object_name) {
+                        Some(object_name) => Some(object_name),
+                        None => return false,
+                    },
+                    None => None,
+                };
+                match self.pronoun(item_name) {
+                    Some(item_name) => self.use_item(&item_name, object_name.as_deref()),
+                    None => false,
+                }
+            },
+            Command::Look => {
+                self.look();
+                true
+            },
+            Command::Map => {
+                self.print_map();
+                true
+            },
+            Command::Inventory => {
+                self.list_inventory_contents();
+                true
+            },
+            Command::Hint => {
+                self.give_hint();
+                true
+            },
+            Command::Score => {
+                self.print_score();
+                true
+            },
+            Command::Help => {
+                self.print_help_text();
+                true
+            },
+            Command::Quit => {
+                self.state.flags.update_key("isGameRunning", false);
+                true
+            },
+            Command::Script(file_name) => self.start_transcript(&file_name),
+            Command::Unscript => self.stop_transcript(),
+            Command::Wizard(spell) => self.cast(spell),
+        }
+    }
+
+    fn change_room(&self, next_room: Option<&'static str>, direction: &Direction) -> &'static str {
+        if !(self.world.room(self.state.current_room).can_move)(&self.state.flags, direction) {
+            sayln!("{}", tr!("move.dead_end"));
+            self.emit(Event::MoveBlocked { room: self.state.current_room, direction: *direction });
+            return self.state.current_room;
+        }
+        match next_room {
+            Some(room_id) => {
+                self.describe_room(room_id);
+                room_id
+            },
+            None => {
+                sayln!("{}", tr!("move.dead_end"));
+                self.emit(Event::MoveBlocked { room: self.state.current_room, direction: *direction });
+                self.state.current_room
+            },
+        }
+    }
+
+    // 'it' and 'them' mean whatever the player last grabbed or used
+    fn pronoun(&self, name: String) -> Option<String> {
+        if !i18n::is_word(&name, "words.it") {
+            return Some(name);
+        }
+        match self.state.last_item {
+            Some(id) => Some(self.world.item(id).name.clone()),
+            None => {
+                sayln!("{}", tr!("pronoun.unknown", name));
+                None
+            },
+        }
+    }
+
+    fn pick_up_item(&mut self, item_name: &str) -> bool {
+        if !self.is_lit(self.state.current_room) {
+            sayln!("{}", tr!("grab.dark"));
+            return false;
+        }
+        let id = {
+            let room_items = self.room_items();
+            match nouns::resolve(&room_items, item_name) {
+                Resolved::Found(i) => room_items[i].id,
+                Resolved::NotFound => {
+                    sayln!("{}", tr!("grab.nothing"));
+                    return false;
+                },
+                Resolved::Cancelled => return false,
+            }
+        };
+
+        let item = self.world.item(id);
+        self.state.last_item = Some(item.id);
+        // checked before on_grab, which would talk as if the item had been picked up
+        if item.is_grabbable && !self.has_room_for(item) {
+            sayln!("{}", tr!("grab.too_heavy", item.name));
+            return false;
+        }
+        // print on_grab message; it may also refuse
+        if !(item.on_grab)(&mut self.state.flags) {
+            return false;
+        }
+        // if item is grabbable, remove from room & add to inventory
+        if item.is_grabbable {
+            self.state.take(id);
+            self.emit(Event::ItemTaken(id));
+        }
+        true
+    }
+
+    // grabs everything in the room that can be carried. Things that can't be
+    // (levers, ropes...) are left alone; they would get pulled on otherwise.
+    fn pick_up_all_items(&mut self, exceptions: &[String]) -> bool {
+        if !self.is_lit(self.state.current_room) {
+            sayln!("{}", tr!("grab.dark"));
+            return false;
+        }
+        let mut found = false;
+        let mut taken = false;
+        for id in self.room_items().iter().map(|item| item.id).collect::<Vec<_>>() {
+            let item = self.world.item(id);
+            if !item.is_grabbable || exceptions.iter().any(|name| nouns::is_match(item, name)) {
+                continue;
+            }
+
+            found = true;
+            say!("{}: ", item.name);
+            // the rest may still fit, so keep going
+            if !self.has_room_for(item) {
+                sayln!("{}", tr!("grab_all.too_heavy"));
+                continue;
+            }
+            self.state.last_item = Some(item.id);
+            // print on_grab message; it may also refuse
+            if (item.on_grab)(&mut self.state.flags) {
+                self.state.take(id);
+                self.emit(Event::ItemTaken(id));
+                taken = true;
+            }
+        }
+
+        if !found {
+            sayln!("{}", tr!("grab_all.nothing"));
+        }
+        taken
+    }
+
+    // puts something from the bag down in the room, to make space for something else
+    fn drop_item(&mut self, item_name: &str) -> bool {
+        let id = {
+            let bag = self.bag();
+            match nouns::resolve(&bag, item_name) {
+                Resolved::Found(i) => bag[i].id,
+                Resolved::NotFound => {
+                    sayln!("{}", tr!("use.not_carried", item_name));
+                    return false;
+                },
+                Resolved::Cancelled => return false,
+            }
+        };
+
+        let item = self.world.item(id);
+        self.state.last_item = Some(item.id);
+        self.state.put_down(id);
+        sayln!("{}", tr!("drop.done", item.name));
+        self.emit(Event::ItemDropped(id));
+        true
+    }
+
+    fn use_item(&mut self, item_name: &str, object_name: Option<&str>) -> bool {
+        // 1) ensure item exists / is found
+        // we need the ID of the item (if we have it in our inventory, that is)
+        let id = {
+            let bag = self.bag();
+            match nouns::resolve(&bag, item_name) {
+                Resolved::Found(x) => bag[x].id,
+                Resolved::NotFound => {
+                    // item wasn't found; give 'not found' msg
+                    sayln!("{}", tr!("use.not_carried", item_name));
+                    return false;
+                },
+                Resolved::Cancelled => return false,
+            }
+        };
+
+        let item = self.world.item(id);
+        self.state.last_item = Some(item.id);
+        if object_name.is_none() && item.light.is_some() {
+            return self.toggle_light(id);
+        }
+        let on_use = match item.on_use {
+            Some(ref on_use) => on_use,
+            None => {
+                sayln!("{}", tr!("use.no_way", item.name));
+                return false;
+            },
+        };
+
+        // 2) work out what it's being used on: something in the bag, something
+        // in the room, or the room itself if nothing was named
+        let object_name = match object_name {
+            Some(object_name) => object_name,
+            None => {
+                let before = self.state.flags.fresh();
+                let used_up = on_use(&mut self.state.flags, &Target::Room(self.state.current_room));
+                if used_up {
+                    self.state.use_up(id);
+                }
+                self.emit(Event::ItemUsed { item: id, on: None });
+                return used_up || self.state.flags != before;
+            },
+        };
+
+        // everything in reach but the item itself
+        let target_id = {
+            let in_reach: Vec<&Item> = self.bag().into_iter()
+                .filter(|other| other.id != id)
+                .chain(self.room_items())
+                .collect();
+            match nouns::resolve(&in_reach, object_name) {
+                Resolved::Found(i) => in_reach[i].id,
+                Resolved::NotFound => {
+                    sayln!("{}", tr!("use.not_here", object_name));
+                    return false;
+                },
+                Resolved::Cancelled => return false,
+            }
+        };
+
+        // 3) keys work the lock of anything they fit, whatever their on_use says.
+        // A tool it takes to break something is left to its on_use, like any other use.
+        let locked = self.state.flags.get_properties(target_id).and_then(|properties| properties.locked);
+        if self.world.item(target_id).keys.contains(&id) && locked.is_some() {
+            let worked = self.act(if locked == Some(true) { Action::Unlock } else { Action::Lock }, target_id);
+            self.emit(Event::ItemUsed { item: id, on: Some(target_id) });
+            return worked;
+        }
+
+        // 4) two things from the bag might make something new
+        if self.state.inventory.contains(&target_id) {
+            if let Some(recipe) = self.world.recipe_for(id, target_id) {
+                (recipe.craft)(&mut self.state.flags);
+                self.state.use_up(id);
+                self.state.use_up(target_id);
+                self.state.inventory.push(recipe.result.id);
+                self.state.last_item = Some(recipe.result.id);
+                self.emit(Event::ItemUsed { item: id, on: Some(target_id) });
+                return true;
+            }
+        }
+
+        // 5) remove item from inventory if it was used up
+        let before = self.state.flags.fresh();
+        let used_up = on_use(&mut self.state.flags, &Target::Item(self.world.item(target_id)));
+        if used_up {
+            self.state.use_up(id);
+        }
+        self.emit(Event::ItemUsed { item: id, on: Some(target_id) });
+        // on_use always has something to say, but it only counts if something changed
+        used_up || self.state.flags != before
+    }
+
+    fn look(&self) {
+        self.describe_room(self.state.current_room);
+    }
+
+    // what the player sees of a room. With a screen reader, nothing stands out
+    // by its colour, so the room's name comes first and the ways out and the
+    // things worth a closer look are repeated afterwards as sentences.
+    fn describe_room(&self, id: &'static str) {
+        let room = self.world.room(id);
+        if self.accessible {
+            sayln!("{}", room.name);
+        }
+        if !self.is_lit(id) {
+            sayln!("{}", tr!("dark.description"));
+            return;
+        }
+        let items = console::noted(|| (room.get_description)(&self.state.flags));
+        // the descriptions only know about what was there to begin with
+        let left: Vec<String> = self.state.items_in(id).iter()
+            .filter(|&&item| self.state.dropped.contains(item))
+            .map(|&item| self.world.item(item))
+            .filter(|item| !items.iter().any(|name| nouns::is_match(item, name)))
+            .map(|item| tr!("describe.item", format!("{{item:{}}}", item.name)))
+            .collect();
+        if !left.is_empty() {
+            sayln!("{}", tr!("describe.left", i18n::list(&left, "list.and")));
+        }
+        if !self.accessible {
+            return;
+        }
+
+        // only the exits the player can take right now; a locked door isn't a way out yet
+        let exits: Vec<String> = room.connections.exits().iter()
+            .filter(|&&(direction, _)| (room.can_move)(&self.state.flags, &direction))
+            .map(|&(direction, _)| direction.local_name().to_string())
+            .collect();
+        if exits.is_empty() {
+            sayln!("{}", tr!("describe.no_exits"));
+        }
+        else {
+            sayln!("{}", tr!("describe.exits", i18n::list(&exits, "list.or")));
+        }
+        if !items.is_empty() {
+            let items: Vec<String> = items.iter().map(|item| tr!("describe.item", item)).collect();
+            sayln!("{}", tr!("describe.items", i18n::list(&items, "list.and")));
+        }
+    }
+
+    fn list_inventory_contents(&self) {
+        say!("{} ", tr!("inventory.intro"));
+        if self.state.inventory.is_empty() {
+            say!("{}", tr!("inventory.empty"));
+        }
+        else {
+            for item in self.bag() {
+                match self.state.flags.get_properties(item.id).and_then(|properties| properties.count) {
+                    Some(count) if count > 1 => say!("{} ", tr!("inventory.count", item.name, count)),
+                    _ => say!("{} ", item.name),
+                }
+            }
+        }
+        sayln!();
+        sayln!("{}", tr!("inventory.load", self.load(), self.world.capacity));
+    }
+
+    fn start_transcript(&self, file_name: &str) -> bool {
+        match console::start_transcript(file_name) {
+            Ok(()) => {
+                sayln!("{}", tr!("script.started", file_name));
+                true
+            },
+            Err(e) => {
+                sayln!("{}", tr!("script.failed", file_name, e));
+                false
+            },
+        }
+    }
+
+    fn stop_transcript(&self) -> bool {
+        // stop first, so the message doesn't end up in the transcript
+        if console::stop_transcript() {
+            sayln!("{}", tr!("script.stopped"));
+            true
+        }
+        else {
+            sayln!("{}", tr!("script.none"));
+            false
+        }
+    }
+
+    fn print_map(&self) {
+        sayln!();
+        for line in map::draw(&self.world, &self.state.visited, self.state.current_room, &self.state.flags) {
+            sayln!("{}", line);
+        }
+        sayln!("\n{}", tr!("map.legend", self.world.room(self.state.current_room).name));
+    }
+
+    // the next clue for the first step the player hasn't done yet. The first
+    // clue for each step is free, the more explicit ones cost points.
+    fn give_hint(&mut self) {
+        let hint = match self.world.hints.iter().position(|hint| (hint.applies)(&self.state.flags, self.state.current_room)) {
+            Some(hint) => hint,
+            None => {
+                sayln!("{}", tr!("hint.none"));
+                return;
+            },
+        };
+
+        let clues = &self.world.hints[hint].clues;
+        let given = self.state.clues_given.entry(hint).or_insert(0);
+        // once they've all been given, the last one is repeated for free
+        let shown = (*given).min(clues.len() - 1);
+        sayln!("{}", tr!("hint.clue", shown + 1, clues.len(), clues[shown]));
+        if *given >= clues.len() {
+            return;
+        }
+        *given += 1;
+
+        if shown > 0 {
+            self.state.score = self.state.score.saturating_sub(HINT_COST);
+            sayln!("{}", tr!("hint.cost", HINT_COST));
+        }
+        else if clues.len() > 1 {
+            sayln!("{}", tr!("hint.more", HINT_COST));
+        }
+    }
+
+    fn print_score(&self) {
+        sayln!("{}", tr!("score", self.state.score, MAX_SCORE));
+    }
+
+    fn print_help_text(&self) {
+        // a screen reader reads brackets, bars and underlines out symbol by
+        // symbol, so accessible mode gets the same help in sentences
+        let title = tr!("help.title");
+        let indent = if self.accessible { "" } else { "         " };
+        let plain = |id: &'static str, plain_id: &'static str| if self.accessible { tr!(plain_id) } else { tr!(id) };
+        sayln!("\n{}", title);
+        if !self.accessible {
+            sayln!("{}", "=".repeat(title.chars().count()));
+        }
+        sayln!("{}", plain("help.actions", "help.actions.plain"));
+        sayln!("{}{}", indent, tr!("help.combine"));
+        sayln!("{}{}", indent, plain("help.grab_all", "help.grab_all.plain"));
+        sayln!("{}{}", indent, tr!("help.drop"));
+        sayln!("{}{}", indent, tr!("help.it"));
+        sayln!("{}", tr!("help.stuck"));
+        sayln!("{}", plain("help.movement", "help.movement.plain"));
+        sayln!("{}", plain("help.system", "help.system.plain"));
+        sayln!("{}", tr!("help.several"));
+        // there's no line editor for them in accessible mode
+        if !self.accessible {
+            sayln!("{}", tr!("help.history"));
+            sayln!("{}", tr!("help.tab"));
+        }
+        if self.wizard {
+            sayln!("WIZARD: teleport <room>, set <flag> true|false, set <item> <property> true|false, flags, spawn <item>, rooms, items");
+        }
+        sayln!();
+    }
+}
+
+// every game starts with full marks; clear hints take some away
+pub const MAX_SCORE: u32 = 100;
+const HINT_COST: u32 = 5;
+
+// how many turns a light has left when the player is warned that it's running out
+const LOW_FUEL: u32 = 10;
+
+// where commands typed in earlier sessions are kept, for the line editor
+const HISTORY_FILE: &str = ".adventure_history";
+
+// exit codes, so that scripts driving the game can tell how it went
+const EXIT_WON: i32 = 0;
+const EXIT_NOT_WON: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+const EXIT_BROKEN_WORLD: i32 = 3;
+
+struct Options {
+    transcript: Option<String>,
+    replay: Option<String>,
+    batch: bool,
+    // only check the world for mistakes, don't play
+    check: bool,
+    // print the world as a Graphviz graph instead of playing
+    dot: bool,
+    // same, but the puzzle chain instead of the rooms
+    puzzles: bool,
+    // print the shortest way to win instead of playing
+    solve: bool,
+    // report every event (see events.rs) on stderr while playing
+    events: bool,
+    // allow the cheats in wizard.rs
+    wizard: bool,
+    // language code; None to go by the environment
+    language: Option<String>,
+    // for screen readers: no screen clearing, title art, colours or line editor
+    accessible: bool,
+}
+
+fn print_usage() {
+    println!("USAGE: adventure [--transcript FILE] [--replay FILE] [--batch] [--check] [--dot] [--puzzles] [--solve] [--events] [--wizard] [--lang CODE] [--accessible]");
+    println!();
+    println!("  --transcript FILE   record everything shown and typed to FILE");
+    println!("  --replay FILE       play the commands saved in FILE (a list of commands");
+    println!("                      or a transcript) before handing over the keyboard");
+    println!("  --batch             no prompts or pauses, quit at the end of the input;");
+    println!("                      the default when input isn't coming from a terminal");
+    println!("  --check             list everything wrong with the game world and quit");
+    println!("  --dot               print the rooms and exits as a Graphviz graph and quit");
+    println!("  --puzzles           print which puzzle steps depend on which as a Graphviz graph and quit");
+    println!("  --solve             print the shortest list of commands that wins the game and quit;");
+    println!("                      the list can be played back with --replay");
+    println!("  --events            report rooms entered, items taken, flags changed and so on");
+    println!("                      on stderr as they happen, for debugging a level");
+    println!("  --wizard            allow commands that cheat (teleport, set, spawn...), for");
+    println!("                      testing a level; type ? in the game to see them");
+    println!("  --lang CODE         the language to play in: {}; the default comes from", i18n::language_codes().join(", "));
+    println!("                      ADVENTURE_LANG, or the system's language if that isn't set");
+    println!("  --accessible        plain output for screen readers: no title art, colours or");
+    println!("                      screen clearing, and every room is named and its exits and");
+    println!("                      items listed in sentences");
+    println!();
+    println!("Exits with {} if the game was won, {} if it wasn't and {} if the game world is broken.", EXIT_WON, EXIT_NOT_WON, EXIT_BROKEN_WORLD);
+}
+
+fn parse_args() -> Options {
+    let mut options = Options { transcript: None, replay: None, batch: !console::is_interactive(), check: false, dot: false, puzzles: false, solve: false, events: false, wizard: false, language: None, accessible: false };
+    let mut args = env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--transcript" => options.transcript = Some(argument(&arg, args.next(), "a file name")),
+            "--replay" => options.replay = Some(argument(&arg, args.next(), "a file name")),
+            "--batch" => options.batch = true,
+            "--check" => options.check = true,
+            "--dot" => options.dot = true,
+            "--puzzles" => options.puzzles = true,
+            "--solve" => options.solve = true,
+            "--events" => options.events = true,
+            "--wizard" => options.wizard = true,
+            "--lang" => options.language = Some(argument(&arg, args.next(), "a language code")),
+            "--accessible" => options.accessible = true,
+            "-h"|"--help" => {
+                print_usage();
+                process::exit(0);
+            },
+            _ => {
+                println!("Unknown option '{}'.", arg);
+                print_usage();
+                process::exit(EXIT_USAGE);
+            },
+        }
+    }
+
+    options
+}
+
+// the history file lives in the player's home directory, if they have one
+fn history_path() -> Option<PathBuf> {
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .map(|home| PathBuf::from(home).join(HISTORY_FILE))
+}
+
+// the value following an option (what it is, for the error), or bail out if it's missing
+fn argument(option: &str, value: Option<String>, what: &str) -> String {
+    match value {
+        Some(ref value) if !value.starts_with('-') => value.clone(),
+        _ => {
+            println!("'{}' needs {}.", option, what);
+            print_usage();
+            process::exit(EXIT_USAGE);
+        },
+    }
+}
+
+// --check; errors and warnings alike
+fn check_world() -> ! {
+    // the handlers can only be gone through once the rest is in one piece
+    let problems = match levels::create_world() {
+        Ok(world) => {
+            let mut problems = validate::check_handlers(&world);
+            problems.extend(world.warnings);
+            problems.sort_by_key(|problem| !problem.is_error());
+            problems
+        },
+        Err(problems) => problems,
+    };
+    for problem in &problems {
+        println!("{}", problem);
+    }
+
+    let errors = problems.iter().filter(|problem| problem.is_error()).count();
+    println!("{} error(s), {} warning(s).", errors, problems.len() - errors);
+    if errors > 0 {
+        process::exit(EXIT_BROKEN_WORLD);
+    }
+    process::exit(0);
+}
+
+// --dot and --puzzles; the world has to be in one piece to be drawn
+fn print_graph(puzzles: bool) -> ! {
+    match levels::create_world() {
+        Ok(world) => {
+            if puzzles {
+                print!("{}", puzzles::to_dot(&world));
+            }
+            else {
+                print!("{}", dot::to_dot(&world.rooms, world.start_room, &world.flags));
+            }
+            process::exit(0);
+        },
+        Err(_) => {
+            println!("The game world is broken; run with --check to see why.");
+            process::exit(EXIT_BROKEN_WORLD);
+        },
+    }
+}
+
+// --solve; exits with EXIT_NOT_WON if the game can't be won
+fn print_walkthrough() -> ! {
+    let world = match levels::create_world() {
+        Ok(world) => world,
+        Err(_) => {
+            println!("The game world is broken; run with --check to see why.");
+            process::exit(EXIT_BROKEN_WORLD);
+        },
+    };
+    let mut game = Game::new(world);
+
+    match solver::solve(&mut game) {
+        solver::Solution::Won(commands) => {
+            println!("# The shortest way to win: {} commands.", commands.len());
+            for command in commands {
+                println!("{}", command);
+            }
+            process::exit(EXIT_WON);
+        },
+        solver::Solution::Unwinnable(states) => {
+            println!("# There is no way to win; {} different states were tried.", states);
+            process::exit(EXIT_NOT_WON);
+        },
+        solver::Solution::GaveUp(states) => {
+            println!("# Gave up after trying {} different states; the game may or may not be winnable.", states);
+            process::exit(EXIT_NOT_WON);
+        },
+    }
+}
+
+// the whole game, as the adventure binary runs it
+pub fn run() {
+    let options = parse_args();
+    // a language the game doesn't have is only a mistake if it was asked for by name
+    match options.language {
+        Some(ref code) => {
+            if !i18n::set_language(code) {
+                println!("There is no '{}' translation; the game can be played in {}.", code, i18n::language_codes().join(", "));
+                process::exit(EXIT_USAGE);
+            }
+        },
+        None => {
+            if let Some(code) = i18n::language_from_env() {
+                i18n::set_language(&code);
+            }
+        },
+    }
+    if options.check {
+        check_world();
+    }
+    if options.solve {
+        print_walkthrough();
+    }
+    if options.dot || options.puzzles {
+        print_graph(options.puzzles);
+    }
+    console::set_batch(options.batch);
+    console::set_accessible(options.accessible);
+    // the line editor redraws the line as it's typed on, which screen readers read out again
+    if !options.batch && !options.accessible {
+        console::start_line_editor(history_path());
+    }
+
+    if let Some(ref file_name) = options.transcript {
+        if let Err(e) = console::start_transcript(file_name) {
+            println!("Could not write the transcript to '{}': {}", file_name, e);
+            process::exit(EXIT_USAGE);
+        }
+    }
+    if let Some(ref file_name) = options.replay {
+        if let Err(e) = console::load_replay(file_name) {
+            println!("Could not read the commands to replay from '{}': {}", file_name, e);
+            process::exit(EXIT_USAGE);
+        }
+    }
+
+    // send a control character to clear terminal screen
+    // source: http://stackoverflow.com/questions/34837011/how-to-clear-terminal-screen-in-rust-after-new-line-is-printing
+    if !options.batch && !options.accessible {
+        print!("{}[2J", 27 as char);
+    }
+
+    // create game
+    let world = match levels::create_world() {
+        Ok(world) => world,
+        Err(problems) => {
+            println!("The game world is broken:");
+            for problem in &problems {
+                println!("  {}", problem);
+            }
+            process::exit(EXIT_BROKEN_WORLD);
+        },
+    };
+    let mut game = Game::new(world);
+    game.wizard = options.wizard;
+    game.accessible = options.accessible;
+    if options.events {
+        game.subscribe(Box::new(|event: &Event| eprintln!("[{}]", event)));
+    }
+
+    // print title screen; a screen reader would read the art out character by character
+    if options.accessible {
+        sayln!("Adventure!");
+    }
+    else {
+        sayln!();
+        sayln!(" ____  ____  _     _____ _      _____ _     ____  _____ _ ");
+        sayln!(r"/  _ \/  _ \/ \ |\/  __// \  /|/__ __Y \ /\/  __\/  __// \\");
+        sayln!(r"| / \|| | \|| | //|  \  | |\ ||  / \ | | |||  \/||  \  | |");
+        sayln!(r"| |-||| |_/|| \// |  /_ | | \||  | | | \_/||    /|  /_ \_/");
+        sayln!(r"\_/ \|\____/\__/  \____\\_/  \|  \_/ \____/\_/\_\\____\(_)");
+        sayln!();
+    }
+    sayln!("{}", tr!("intro"));
+
+    console::prompt(&format!("{}\n", tr!("prompt.begin")));
+    console::pause();
+
+    game.process_command(Command::Look); // print initial room description
+
+    // main game loop
+    while game.state.is_running() {
+        let commands = game.get_commands();
+        game.run_commands(commands);
+    }
+
+    if game.state.is_won() {
+        game.print_score();
+        console::prompt(&format!("{}\n", tr!("prompt.exit")));
+        console::pause();
+        process::exit(EXIT_WON);
+    }
+    process::exit(EXIT_NOT_WON);
+}
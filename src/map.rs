@@ -0,0 +1,162 @@
+/*
+Draws the rooms the player has been to as a grid. Rooms don't have coordinates,
+so they are laid out by walking the connections from the starting room: north
+of a room is one row up, east is one column to the right and so on. Rooms the
+player hasn't seen yet are left blank, and an exit they could take but haven't
+is drawn as a '?'.
+
+   [Well]--------[Crossroads]-------[Shack]
+                      |
+                *Greenhouse*
+                      |
+                   [Cell]
+*/
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use Direction;
+use Flags;
+use world::World;
+
+// space between two columns, for the '-----'
+const GAP: usize = 5;
+
+// where every room goes, as (column, row); the starting room is at (0, 0)
+fn place_rooms(world: &World) -> HashMap<&'static str, (i32, i32)> {
+    let mut positions = HashMap::new();
+    let mut to_place = VecDeque::new();
+    positions.insert(world.start_room, (0, 0));
+    to_place.push_back(world.start_room);
+
+    while let Some(id) = to_place.pop_front() {
+        let (x, y) = positions[id];
+        for (direction, to) in world.room(id).connections.exits() {
+            if positions.contains_key(to) {
+                continue;
+            }
+            let position = match direction {
+                Direction::North => (x, y - 1),
+                Direction::South => (x, y + 1),
+                Direction::East => (x + 1, y),
+                Direction::West => (x - 1, y),
+            };
+            // two rooms that want the same spot; the first one keeps it
+            if positions.values().any(|&taken| taken == position) {
+                continue;
+            }
+            positions.insert(to, position);
+            to_place.push_back(to);
+        }
+    }
+
+    positions
+}
+
+enum Link {
+    None,
+    // both rooms have been visited
+    Known,
+    // one of them hasn't, but the player could go there right now
+    Unexplored,
+}
+
+// how the rooms at two neighbouring spots are connected; direction leads from
+// the first spot to the second
+fn link(world: &World, visited: &BTreeSet<&'static str>, flags: &Flags, from: Option<&'static str>, to: Option<&'static str>, direction: Direction) -> Link {
+    let (from, to) = match (from, to) {
+        (Some(from), Some(to)) => (from, to),
+        _ => return Link::None,
+    };
+    let from_room = world.room(from);
+    let to_room = world.room(to);
+    let goes_there = from_room.connections.get(direction) == Some(to);
+    let comes_back = to_room.connections.get(opposite(direction)) == Some(from);
+
+    match (visited.contains(from), visited.contains(to)) {
+        (true, true) if goes_there || comes_back => Link::Known,
+        (true, false) if goes_there && (from_room.can_move)(flags, &direction) => Link::Unexplored,
+        (false, true) if comes_back && (to_room.can_move)(flags, &opposite(direction)) => Link::Unexplored,
+        _ => Link::None,
+    }
+}
+
+fn opposite(direction: Direction) -> Direction {
+    match direction {
+        Direction::North => Direction::South,
+        Direction::South => Direction::North,
+        Direction::East => Direction::West,
+        Direction::West => Direction::East,
+    }
+}
+
+// the map, one line per row of text
+pub fn draw(world: &World, visited: &BTreeSet<&'static str>, current_room: &'static str, flags: &Flags) -> Vec<String> {
+    let positions = place_rooms(world);
+    let at: HashMap<(i32, i32), &'static str> = positions.iter().map(|(&id, &position)| (position, id)).collect();
+    let shown: Vec<(i32, i32)> = positions.iter()
+        .filter(|&(id, _)| visited.contains(id))
+        .map(|(_, &position)| position)
+        .collect();
+    if shown.is_empty() {
+        return Vec::new();
+    }
+
+    // leave room around the edges for exits that lead off the explored part
+    let min_x = shown.iter().map(|&(x, _)| x).min().unwrap_or(0) - 1;
+    let max_x = shown.iter().map(|&(x, _)| x).max().unwrap_or(0) + 1;
+    let min_y = shown.iter().map(|&(_, y)| y).min().unwrap_or(0) - 1;
+    let max_y = shown.iter().map(|&(_, y)| y).max().unwrap_or(0) + 1;
+    let width = visited.iter().map(|id| world.room(id).name.chars().count()).max().unwrap_or(0) + 2;
+    let room_at = |x: i32, y: i32| at.get(&(x, y)).cloned();
+
+    let mut lines = Vec::new();
+    for y in min_y ..= max_y {
+        let mut rooms_line = String::new();
+        let mut links_line = String::new();
+        for x in min_x ..= max_x {
+            let label = match room_at(x, y) {
+                Some(id) if id == current_room => format!("*{}*", world.room(id).name),
+                Some(id) if visited.contains(id) => format!("[{}]", world.room(id).name),
+                _ => String::new(),
+            };
+            let west = link(world, visited, flags, room_at(x - 1, y), room_at(x, y), Direction::East);
+            let east = link(world, visited, flags, room_at(x, y), room_at(x + 1, y), Direction::East);
+
+            // run the connections right up to the label
+            let padding = width - label.chars().count();
+            let fill = |link: &Link| if label.is_empty() || matches!(*link, Link::None) { " " } else { "-" };
+            rooms_line.push_str(&fill(&west).repeat(padding / 2));
+            rooms_line.push_str(&label);
+            rooms_line.push_str(&fill(&east).repeat(padding - padding / 2));
+            rooms_line.push_str(match east {
+                Link::Known => "-----",
+                Link::Unexplored => "--?--",
+                Link::None => "     ",
+            });
+
+            let south = match link(world, visited, flags, room_at(x, y), room_at(x, y + 1), Direction::South) {
+                Link::Known => "|",
+                Link::Unexplored => "?",
+                Link::None => "",
+            };
+            links_line.push_str(&format!("{:^1$}", south, width));
+            links_line.push_str(&" ".repeat(GAP));
+        }
+        lines.push(rooms_line.trim_end().to_string());
+        lines.push(links_line.trim_end().to_string());
+    }
+
+    // the extra rows around the edges are usually empty
+    while lines.first().is_some_and(|line| line.is_empty()) {
+        lines.remove(0);
+    }
+    while lines.last().is_some_and(|line| line.is_empty()) {
+        lines.pop();
+    }
+
+    // and so are the extra columns
+    let indent = lines.iter()
+        .filter(|line| !line.is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    lines.iter().map(|line| line.get(indent ..).unwrap_or("").to_string()).collect()
+}
@@ -0,0 +1,220 @@
+/*
+Markup for the text in levels.rs, so that the names of things stand out:
+
+    sayln!("On top of the table is a fat {item:cat}, staring intently at you.");
+    sayln!("A passageway has been revealed on the {exit:north} wall.");
+
+In a terminal, items and exits come out bold and in colour. Anywhere else (a
+pipe, a transcript) there's no colour to be had, so they are written in capitals
+instead, the way all the text used to be. Screen readers get them as they are
+(see --accessible). Anything in braces that isn't one of these is left alone.
+
+Long lines are wrapped to the width of the terminal by Wrapper, which has to
+remember where the cursor is because a line is often written in pieces.
+*/
+
+const ITEM_STYLE: &str = "\x1b[1;33m"; // bold yellow
+const EXIT_STYLE: &str = "\x1b[1;36m"; // bold cyan
+const RESET: &str = "\x1b[0m";
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Style {
+    Colour,
+    Capitals,
+    // no markup at all; capitals get spelled out by some screen readers
+    Plain,
+}
+
+// the text with the markup taken out, and the names in the given style
+pub fn render(text: &str, style: Style) -> String {
+    let mut rendered = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[.. start]);
+        rest = &rest[start ..];
+        let markup = rest.find('}').and_then(|end| parse_tag(&rest[1 .. end]).map(|tag| (tag, end)));
+        match markup {
+            Some(((kind, name), end)) => {
+                match style {
+                    Style::Colour => {
+                        rendered.push_str(if kind == "item" { ITEM_STYLE } else { EXIT_STYLE });
+                        rendered.push_str(name);
+                        rendered.push_str(RESET);
+                    },
+                    Style::Capitals => rendered.push_str(&name.to_uppercase()),
+                    Style::Plain => rendered.push_str(name),
+                }
+                rest = &rest[end + 1 ..];
+            },
+            None => {
+                rendered.push('{');
+                rest = &rest[1 ..];
+            },
+        }
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+// the names of the items marked up in text, in order
+pub fn items(text: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        rest = &rest[start + 1 ..];
+        if let Some(end) = rest.find('}') {
+            if let Some(("item", name)) = parse_tag(&rest[.. end]) {
+                items.push(name);
+            }
+        }
+    }
+    items
+}
+
+// "item:cat" -> ("item", "cat")
+fn parse_tag(tag: &str) -> Option<(&str, &str)> {
+    let colon = tag.find(':')?;
+    let (kind, name) = (&tag[.. colon], &tag[colon + 1 ..]);
+    if (kind == "item" || kind == "exit") && !name.is_empty() {
+        Some((kind, name))
+    }
+    else {
+        None
+    }
+}
+
+// how many columns text takes up on screen; escape codes take none
+fn visible_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut in_escape = false;
+    for c in text.chars() {
+        if in_escape {
+            in_escape = c != 'm';
+        }
+        else if c == '\x1b' {
+            in_escape = true;
+        }
+        else {
+            width += 1;
+        }
+    }
+    width
+}
+
+pub struct Wrapper {
+    // how far along the current line the cursor is
+    column: usize,
+}
+
+impl Wrapper {
+    pub const fn new() -> Wrapper {
+        Wrapper { column: 0 }
+    }
+
+    // text, with line breaks put in before words that would run past width.
+    // Spaces are kept as they are, so that indented text (the map) stays put.
+    pub fn wrap(&mut self, text: &str, width: usize) -> String {
+        let mut wrapped = String::new();
+        let mut word = String::new();
+        for c in text.chars() {
+            if c == ' ' || c == '\n' {
+                self.push_word(&mut wrapped, &word, width);
+                word.clear();
+                if c == '\n' {
+                    wrapped.push('\n');
+                    self.column = 0;
+                }
+                // a space right at the edge would wrap by itself in the terminal
+                else if self.column < width {
+                    wrapped.push(' ');
+                    self.column += 1;
+                }
+            }
+            else {
+                word.push(c);
+            }
+        }
+        self.push_word(&mut wrapped, &word, width);
+        wrapped
+    }
+
+    fn push_word(&mut self, wrapped: &mut String, word: &str, width: usize) {
+        let length = visible_width(word);
+        if length == 0 {
+            // an escape code on its own still has to go out
+            wrapped.push_str(word);
+            return;
+        }
+        if self.column > 0 && self.column + length > width {
+            // the spaces that were meant to go before the word end the line instead
+            while wrapped.ends_with(' ') {
+                wrapped.pop();
+            }
+            wrapped.push('\n');
+            self.column = 0;
+        }
+        wrapped.push_str(word);
+        self.column += length;
+    }
+
+    // the player pressed enter, so the cursor is at the start of a line
+    pub fn new_line(&mut self) {
+        self.column = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_in_each_style() {
+        let text = "A fat {item:cat} to the {exit:north}.";
+        assert_eq!(render(text, Style::Capitals), "A fat CAT to the NORTH.");
+        assert_eq!(render(text, Style::Plain), "A fat cat to the north.");
+        assert_eq!(render(text, Style::Colour), format!("A fat {}cat{} to the {}north{}.", ITEM_STYLE, RESET, EXIT_STYLE, RESET));
+    }
+
+    #[test]
+    fn other_braces_are_left_alone() {
+        assert_eq!(render("{0} and {thing:cat} and {item:}", Style::Capitals), "{0} and {thing:cat} and {item:}");
+        assert_eq!(render("an open { with no end", Style::Capitals), "an open { with no end");
+        assert_eq!(items("{item:cat}, {exit:north} and {item:lever}"), vec!["cat", "lever"]);
+    }
+
+    #[test]
+    fn wraps_before_words_that_run_over() {
+        let mut wrapper = Wrapper::new();
+        assert_eq!(wrapper.wrap("the quick brown fox", 10), "the quick\nbrown fox");
+    }
+
+    #[test]
+    fn escape_codes_take_no_room() {
+        let mut wrapper = Wrapper::new();
+        let text = render("a {item:cat} sat", Style::Colour);
+        assert_eq!(visible_width(&text), 9);
+        assert_eq!(wrapper.wrap(&text, 9), text);
+    }
+
+    #[test]
+    fn a_line_written_in_pieces() {
+        let mut wrapper = Wrapper::new();
+        assert_eq!(wrapper.wrap("the quick ", 10), "the quick ");
+        // the cursor is still on the same line, ten columns along
+        assert_eq!(wrapper.wrap("brown", 10), "\nbrown");
+        wrapper.new_line();
+        assert_eq!(wrapper.wrap("fox", 10), "fox");
+    }
+
+    #[test]
+    fn a_word_longer_than_the_line_gets_one_to_itself() {
+        let mut wrapper = Wrapper::new();
+        assert_eq!(wrapper.wrap("a greenhouse door", 5), "a\ngreenhouse\ndoor");
+    }
+
+    #[test]
+    fn line_breaks_start_over() {
+        let mut wrapper = Wrapper::new();
+        assert_eq!(wrapper.wrap("one two\nthree four", 9), "one two\nthree\nfour");
+    }
+}
@@ -0,0 +1,175 @@
+/*
+Working out which item the player means. Names don't have to be typed out in
+full: any item whose name or alias contains all the typed words (or the start
+of them) counts, so "glass" finds the glass door. If that leaves more than one
+item, the player is asked which one they meant.
+*/
+use Item;
+use console;
+use i18n;
+
+pub enum Resolved {
+    Found(usize),
+    NotFound,
+    // the player was asked which one they meant and didn't pick one
+    Cancelled,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Quality {
+    Partial,
+    Exact,
+}
+
+// leaves out the articles, which don't help tell items apart. Apostrophes
+// split words too, for "l'autel".
+fn words(phrase: &str) -> Vec<String> {
+    let articles = i18n::all_words("words.articles");
+    phrase.to_lowercase()
+        .split(|c: char| c.is_whitespace() || c == '\'')
+        .filter(|word| !word.is_empty() && !articles.contains(word))
+        .map(|word| word.to_string())
+        .collect()
+}
+
+fn match_quality(item: &Item, typed: &[String]) -> Option<Quality> {
+    let mut best = None;
+    for name in Some(&item.name).into_iter().chain(item.aliases.iter()) {
+        let name_words = words(name);
+        if name_words == typed {
+            return Some(Quality::Exact);
+        }
+        if typed.iter().all(|t| name_words.iter().any(|n| n.starts_with(t.as_str()))) {
+            best = Some(Quality::Partial);
+        }
+    }
+    best
+}
+
+// whether typed could be referring to item
+pub fn is_match(item: &Item, typed: &str) -> bool {
+    let typed = words(typed);
+    !typed.is_empty() && match_quality(item, &typed).is_some()
+}
+
+// whether resolve() would find exactly one item without having to ask
+pub fn is_unambiguous(items: &[&Item], typed: &str) -> bool {
+    best_matches(items, &words(typed)).len() == 1
+}
+
+// indices of the items that fit best; exact matches beat partial ones
+fn best_matches(items: &[&Item], typed: &[String]) -> Vec<usize> {
+    if typed.is_empty() {
+        return Vec::new();
+    }
+
+    let qualities: Vec<Option<Quality>> = items.iter().map(|item| match_quality(item, typed)).collect();
+    let best = if qualities.contains(&Some(Quality::Exact)) { Quality::Exact } else { Quality::Partial };
+    qualities.iter()
+        .enumerate()
+        .filter(|&(_, quality)| *quality == Some(best))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+// "the rusty key, the silver key or the golden key"
+fn list_choices(items: &[&Item], choices: &[usize]) -> String {
+    let names: Vec<String> = choices.iter().map(|&i| tr!("which.choice", items[i].name)).collect();
+    i18n::list(&names, "list.or")
+}
+
+// finds the item in items that the player meant by typed, asking them if
+// there's more than one it could be. Returns an index into items.
+pub fn resolve(items: &[&Item], typed: &str) -> Resolved {
+    let matches = best_matches(items, &words(typed));
+    match matches.len() {
+        0 => return Resolved::NotFound,
+        1 => return Resolved::Found(matches[0]),
+        _ => {},
+    }
+
+    sayln!("{}", tr!("which.question", list_choices(items, &matches)));
+    let answer = match console::read_line("> ") {
+        Some(answer) => answer,
+        None => return Resolved::Cancelled,
+    };
+
+    // only look among the items we asked about
+    let candidates: Vec<&Item> = matches.iter().map(|&i| items[i]).collect();
+    let chosen = best_matches(&candidates, &words(&answer));
+    if chosen.len() == 1 {
+        Resolved::Found(matches[chosen[0]])
+    }
+    else {
+        sayln!("{}", tr!("which.never_mind"));
+        Resolved::Cancelled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Properties;
+
+    fn item(id: &'static str, name: &str, aliases: &[&str]) -> Item {
+        Item {
+            id,
+            name: name.to_string(),
+            aliases: aliases.iter().map(|alias| alias.to_string()).collect(),
+            is_grabbable: true,
+            weight: 1,
+            properties: Properties::NONE,
+            keys: Vec::new(),
+            on_grab: Box::new(|_flags| true),
+            on_use: None,
+            light: None,
+        }
+    }
+
+    #[test]
+    fn exact_matches_beat_partial_ones() {
+        let door = item("door", "door", &[]);
+        let glass_door = item("glass_door", "glass door", &[]);
+        let items = vec![&glass_door, &door];
+        assert_eq!(best_matches(&items, &words("door")), vec![1]);
+        assert_eq!(best_matches(&items, &words("glass door")), vec![0]);
+    }
+
+    #[test]
+    fn the_start_of_a_word_is_enough() {
+        let door = item("door", "door", &[]);
+        let glass_door = item("glass_door", "glass door", &[]);
+        let items = vec![&glass_door, &door];
+        assert_eq!(best_matches(&items, &words("glass")), vec![0]);
+        assert_eq!(best_matches(&items, &words("gl do")), vec![0]);
+        // both are doors, but "do" isn't all of either name
+        assert_eq!(best_matches(&items, &words("do")), vec![0, 1]);
+        assert!(!is_unambiguous(&items, "do"));
+        assert!(best_matches(&items, &words("window")).is_empty());
+    }
+
+    #[test]
+    fn aliases_count_too() {
+        let key = item("key", "key", &[]);
+        let necklace = item("key_necklace", "key necklace", &["necklace"]);
+        let items = vec![&key, &necklace];
+        assert_eq!(best_matches(&items, &words("key")), vec![0]);
+        assert_eq!(best_matches(&items, &words("neck")), vec![1]);
+        assert!(is_match(&necklace, "necklace"));
+    }
+
+    #[test]
+    fn articles_and_apostrophes_are_left_out() {
+        let key = item("key", "key", &[]);
+        assert!(is_match(&key, "the key"));
+        assert!(!is_match(&key, "the"));
+
+        i18n::set_language("fr");
+        assert_eq!(words("l'autel"), vec!["autel"]);
+        let altar = item("altar", "autel", &[]);
+        let other = item("other", "autel de pierre", &[]);
+        let items = vec![&other, &altar];
+        assert_eq!(best_matches(&items, &words("l'autel")), vec![1]);
+        i18n::set_language("en");
+    }
+}
@@ -0,0 +1,439 @@
+/*
+Turning what the player typed into Commands. A line can hold several commands,
+separated by periods, commas or 'then': "go north. grab shovel, go north".
+The words themselves come from the language files (see i18n.rs); below, verbs
+go by their English names.
+*/
+use std::fmt;
+use i18n;
+use properties::Action;
+use Command;
+use Direction;
+use wizard;
+
+// where the 'script' command writes to if no file name is given
+const DEFAULT_TRANSCRIPT: &str = "transcript.txt";
+
+// every verb, and the list of words for it in the language files
+const VERBS: [(&str, &str); 18] = [
+    ("go", "words.go"),
+    ("grab", "words.grab"),
+    ("drop", "words.drop"),
+    ("use", "words.use"),
+    ("open", "words.open"),
+    ("close", "words.close"),
+    ("lock", "words.lock"),
+    ("unlock", "words.unlock"),
+    ("light", "words.light"),
+    ("break", "words.break"),
+    ("inventory", "words.inventory"),
+    ("look", "words.look"),
+    ("map", "words.map"),
+    ("hint", "words.hint"),
+    ("score", "words.score"),
+    ("quit", "words.quit"),
+    ("script", "words.script"),
+    ("unscript", "words.unscript"),
+];
+const DIRECTIONS: [(Direction, &str); 4] = [
+    (Direction::North, "words.north"),
+    (Direction::South, "words.south"),
+    (Direction::East, "words.east"),
+    (Direction::West, "words.west"),
+];
+
+// the English name of the verb word stands for, in whatever language it's in
+pub fn verb(word: &str) -> Option<&'static str> {
+    VERBS.iter().find(|&&(_, words)| i18n::is_word(word, words)).map(|&(verb, _)| verb)
+}
+
+pub fn is_verb(word: &str) -> bool {
+    verb(word).is_some() || word == "?" || wizard::is_verb(word)
+}
+
+fn direction(word: &str) -> Option<Direction> {
+    DIRECTIONS.iter().find(|&&(_, words)| i18n::is_word(word, words)).map(|&(direction, _)| direction)
+}
+
+// what typos are compared against; abbreviations are too short to be useful there
+fn full_words(lists: &[&'static str], words: fn(&'static str) -> Vec<&'static str>) -> Vec<&'static str> {
+    lists.iter()
+        .flat_map(|&list| words(list))
+        .filter(|word| word.chars().count() > 2)
+        .collect()
+}
+
+// splits a line into one string per command. A piece that doesn't start with a
+// verb belongs to the command before it, which keeps lists like "grab all
+// except cat, lever" in one piece. Periods only count when followed by a
+// space, so "script log.txt" stays in one piece too.
+pub fn split_commands(raw_value: &str) -> Vec<String> {
+    let mut pieces: Vec<String> = Vec::new();
+    let mut separated_by_comma: Vec<bool> = Vec::new();
+    let mut current = String::new();
+
+    // each piece remembers whether a comma (rather than a period) came before it
+    separated_by_comma.push(false);
+    let mut chars = raw_value.chars().peekable();
+    while let Some(c) = chars.next() {
+        let is_separator = c == ',' || (c == '.' && chars.peek().is_none_or(|next| next.is_whitespace()));
+        if is_separator {
+            pieces.push(current.clone());
+            separated_by_comma.push(c == ',');
+            current.clear();
+        }
+        else {
+            current.push(c);
+        }
+    }
+    pieces.push(current);
+
+    // "then" works like a period
+    let mut commands: Vec<String> = Vec::new();
+    for (piece, &comma) in pieces.iter().zip(separated_by_comma.iter()) {
+        let words: Vec<&str> = piece.split_whitespace().collect();
+        let mut command: Vec<&str> = Vec::new();
+        let mut clauses: Vec<String> = Vec::new();
+        for word in words {
+            if i18n::is_word(word, "words.then") {
+                clauses.push(command.join(" "));
+                command.clear();
+            }
+            else {
+                command.push(word);
+            }
+        }
+        clauses.push(command.join(" "));
+
+        for (i, clause) in clauses.into_iter().enumerate() {
+            if clause.is_empty() {
+                continue;
+            }
+            let starts_with_verb = clause.split_whitespace().next().is_some_and(is_verb);
+            match commands.last_mut() {
+                // glue it back onto the command it came from
+                Some(last) if i == 0 && !starts_with_verb => {
+                    last.push_str(if comma { ", " } else { ". " });
+                    last.push_str(&clause);
+                },
+                _ => commands.push(clause),
+            }
+        }
+    }
+
+    commands
+}
+
+// what went wrong with a command, in enough detail to tell the player
+pub enum ParseError {
+    Empty,
+    UnknownVerb(String, Option<&'static str>), // with a guess at what was meant
+    MissingItem(String), // the verb that needs one, as it was typed
+    MissingDirection,
+    UnknownDirection(String, Option<&'static str>),
+    MissingTarget(String), // the item that's being used
+    MissingExceptions,
+    MissingValue(String), // the flag that's being set, or the item and property
+    // the start of the command made sense but the rest didn't
+    Unexpected(String, String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseError::Empty => write!(f, "{}", tr!("parse.empty")),
+            ParseError::UnknownVerb(ref verb, Some(suggestion)) => write!(f, "{}", tr!("parse.unknown_verb_guess", verb, suggestion)),
+            ParseError::UnknownVerb(ref verb, None) => write!(f, "{}", tr!("parse.unknown_verb", verb)),
+            ParseError::MissingItem(ref verb) => write!(f, "{}", tr!("parse.missing_item", capitalize(verb))),
+            ParseError::MissingDirection => write!(f, "{}", tr!("parse.missing_direction")),
+            ParseError::UnknownDirection(ref direction, Some(suggestion)) => write!(f, "{}", tr!("parse.unknown_direction_guess", direction, suggestion)),
+            ParseError::UnknownDirection(ref direction, None) => write!(f, "{}", tr!("parse.unknown_direction", direction)),
+            ParseError::MissingTarget(ref item_name) => write!(f, "{}", tr!("parse.missing_target", item_name)),
+            ParseError::MissingExceptions => write!(f, "{}", tr!("parse.missing_exceptions")),
+            ParseError::MissingValue(ref flag) => write!(f, "{}", tr!("parse.missing_value", flag)),
+            ParseError::Unexpected(ref understood, ref rest) => write!(f, "{}", tr!("parse.unexpected", understood, rest)),
+        }
+    }
+}
+
+impl ParseError {
+    // whether the message asks the player for the missing piece of the command
+    pub fn is_question(&self) -> bool {
+        matches!(*self, ParseError::MissingItem(_) | ParseError::MissingDirection | ParseError::MissingTarget(_) | ParseError::MissingExceptions | ParseError::MissingValue(_))
+    }
+
+    // for errors where a piece of the command is missing, turns the player's
+    // answer to the question into the complete command
+    pub fn complete(&self, raw_value: &str, answer: &str) -> Option<String> {
+        let raw_value = raw_value.trim();
+        match *self {
+            ParseError::MissingItem(_) | ParseError::MissingDirection | ParseError::MissingExceptions | ParseError::MissingValue(_) => Some(format!("{} {}", raw_value, answer)),
+            // only asked when the command already ends in "on"
+            ParseError::MissingTarget(_) => Some(format!("{} {}", raw_value, answer)),
+            _ => None,
+        }
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+// how many single-letter edits it takes to turn one word into the other
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0 .. b.len() + 1).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + if a_char == *b_char { 0 } else { 1 };
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+// the closest word to typed, if any are within edits of it
+fn closest(typed: &str, words: &[&'static str], edits: usize) -> Option<&'static str> {
+    words.iter()
+        .map(|word| (edit_distance(typed, word), *word))
+        .filter(|&(distance, _)| distance <= edits && distance < typed.len())
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, word)| word)
+}
+
+// the word from the lists that typed was probably meant to be. The player's own
+// language comes first; English only if nothing in it is close, and only for a
+// single slip, or a French player typing nonsense would be offered English.
+fn suggest(typed: &str, lists: &[&'static str]) -> Option<&'static str> {
+    closest(typed, &full_words(lists, i18n::words), 2).or_else(|| closest(typed, &full_words(lists, i18n::all_words), 1))
+}
+
+// turns a single command into a Command
+pub fn parse(raw_value: &str) -> Result<Command, ParseError> {
+    // variable binding shadowing & convert to lowervase
+    let value = raw_value.to_lowercase();
+
+    // split input into vector of string slices
+    let container: Vec<&str> = value.split_whitespace().collect();
+    if container.is_empty() {
+        return Err(ParseError::Empty);
+    }
+
+    if container[0] == "?" {
+        return Ok(Command::Help);
+    }
+    let verb = match verb(container[0]) {
+        Some(verb) => verb,
+        None => return Err(ParseError::UnknownVerb(container[0].to_string(), suggest(container[0], &VERBS.iter().map(|&(_, words)| words).collect::<Vec<_>>()))),
+    };
+
+    // match on first command
+    match verb {
+        "go" => {
+            // syntax: go DIRECTION.
+            // if container.len() < 2, only one word was entered.
+            if container.len() < 2 {
+                return Err(ParseError::MissingDirection);
+            }
+            let direction = match direction(container[1]) {
+                Some(direction) => direction,
+                None => {
+                    let suggestion = suggest(container[1], &DIRECTIONS.iter().map(|&(_, words)| words).collect::<Vec<_>>());
+                    return Err(ParseError::UnknownDirection(container[1].to_string(), suggestion));
+                },
+            };
+            if container.len() > 2 {
+                return Err(ParseError::Unexpected(container[.. 2].join(" "), container[2 ..].join(" ")));
+            }
+            Ok(Command::Walk(direction))
+        },
+        "grab" => {
+            // syntax: grab ITEM_NAME.
+            // if container.len() < 2, only one word was entered.
+            if container.len() < 2 {
+                Err(ParseError::MissingItem(container[0].to_string()))
+            }
+            else if i18n::is_word(container[1], "words.all") {
+                // syntax: grab all [except ITEM_NAME, ITEM_NAME and ITEM_NAME]
+                if container.len() == 2 {
+                    return Ok(Command::GrabAll(Vec::new()));
+                }
+                if !i18n::is_word(container[2], "words.except") {
+                    return Err(ParseError::Unexpected(container[.. 2].join(" "), container[2 ..].join(" ")));
+                }
+                let mut exceptions: Vec<String> = Vec::new();
+                for names in container[3 ..].join(" ").split(',') {
+                    let mut name: Vec<&str> = Vec::new();
+                    for word in names.split_whitespace() {
+                        if i18n::is_word(word, "words.and") {
+                            exceptions.push(name.join(" "));
+                            name.clear();
+                        }
+                        else {
+                            name.push(word);
+                        }
+                    }
+                    exceptions.push(name.join(" "));
+                }
+                exceptions.retain(|name| !name.is_empty());
+                if exceptions.is_empty() {
+                    return Err(ParseError::MissingExceptions);
+                }
+                Ok(Command::GrabAll(exceptions))
+            }
+            else {
+                let item_name = container[1 .. container.len()].join(" ");
+                Ok(Command::Grab(item_name))
+            }
+        },
+        "drop" => {
+            // syntax: drop ITEM_NAME
+            if container.len() < 2 {
+                return Err(ParseError::MissingItem(container[0].to_string()));
+            }
+            Ok(Command::Drop(container[1 ..].join(" ")))
+        },
+        "open" | "close" | "lock" | "unlock" | "light" | "break" => {
+            // syntax: open ITEM_NAME (and the same for the others)
+            if container.len() < 2 {
+                return Err(ParseError::MissingItem(container[0].to_string()));
+            }
+            let action = match verb {
+                "open" => Action::Open,
+                "close" => Action::Close,
+                "lock" => Action::Lock,
+                "unlock" => Action::Unlock,
+                "light" => Action::Light,
+                _ => Action::Break,
+            };
+            Ok(Command::Handle(action, container[1 ..].join(" ")))
+        },
+        "use" => {
+            // syntax: use ITEM_NAME [on OBJECT]
+            // find the last "on"; the item's name can't start with it
+            let on_index = container.iter().rposition(|word| i18n::is_word(word, "words.on")).filter(|&i| i > 1);
+            let item_end = on_index.unwrap_or(container.len());
+            // "use" or "use on door"
+            if item_end < 2 || i18n::is_word(container[1], "words.on") {
+                return Err(ParseError::MissingItem(container[0].to_string()));
+            }
+            let item_name = container[1 .. item_end].join(" ");
+
+            match on_index {
+                Some(i) if i + 1 < container.len() => {
+                    let object_name = container[i + 1 .. container.len()].join(" ");
+                    Ok(Command::Use(item_name, Some(object_name)))
+                },
+                // "use shovel on"
+                Some(_) => Err(ParseError::MissingTarget(item_name)),
+                // "use shovel"; used on the room itself
+                None => Ok(Command::Use(item_name, None)),
+            }
+        }
+        "inventory" => Ok(Command::Inventory),
+        "look" => Ok(Command::Look),
+        "map" => Ok(Command::Map),
+        "hint" => Ok(Command::Hint),
+        "score" => Ok(Command::Score),
+        "quit" => Ok(Command::Quit),
+        "script" => {
+            // syntax: script [FILE_NAME]
+            let file_name = raw_value.trim()[container[0].len() ..].trim();
+            if file_name.is_empty() {
+                Ok(Command::Script(DEFAULT_TRANSCRIPT.to_string()))
+            }
+            else {
+                Ok(Command::Script(file_name.to_string()))
+            }
+        },
+        _ => Ok(Command::Unscript),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the action and item name that text parses to, if it's one of the verbs in properties.rs
+    fn handle(text: &str) -> Option<(Action, String)> {
+        match parse(text) {
+            Ok(Command::Handle(action, item_name)) => Some((action, item_name)),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn property_verbs() {
+        assert!(handle("open door") == Some((Action::Open, "door".to_string())));
+        assert!(handle("close door") == Some((Action::Close, "door".to_string())));
+        assert!(handle("lock door") == Some((Action::Lock, "door".to_string())));
+        assert!(handle("unlock door") == Some((Action::Unlock, "door".to_string())));
+        assert!(handle("light torch") == Some((Action::Light, "torch".to_string())));
+        assert!(handle("break glass door") == Some((Action::Break, "glass door".to_string())));
+    }
+
+    #[test]
+    fn property_verbs_ignore_case() {
+        assert!(handle("Unlock Shack Door") == Some((Action::Unlock, "shack door".to_string())));
+    }
+
+    #[test]
+    fn property_verbs_need_an_item() {
+        for verb in &["open", "close", "lock", "unlock", "light", "break"] {
+            match parse(verb) {
+                Err(ParseError::MissingItem(ref typed)) => assert_eq!(typed, verb),
+                _ => panic!("'{}' on its own should ask what to {}", verb, verb),
+            }
+        }
+    }
+
+    // the word the game would suggest for an unknown verb
+    fn suggestion(text: &str) -> Option<&'static str> {
+        match parse(text) {
+            Err(ParseError::UnknownVerb(_, suggestion)) => suggestion,
+            _ => panic!("'{}' should be an unknown verb", text),
+        }
+    }
+
+    #[test]
+    fn suggestions_come_from_the_players_language() {
+        assert_eq!(suggestion("gab shovel"), Some("grab"));
+        i18n::set_language("fr");
+        assert_eq!(suggestion("rgarder"), Some("regarder"));
+        assert_eq!(suggestion("ouvrirr porte"), Some("ouvrir"));
+        // nothing French is close, but the English word still works
+        assert_eq!(suggestion("gab pelle"), Some("grab"));
+        assert_eq!(suggestion("foo"), None);
+        i18n::set_language("en");
+    }
+
+    #[test]
+    fn periods_and_commas_split_commands() {
+        assert_eq!(split_commands("go north. grab shovel, go north"), vec!["go north", "grab shovel", "go north"]);
+        assert_eq!(split_commands("look."), vec!["look"]);
+    }
+
+    #[test]
+    fn pieces_without_a_verb_stay_with_the_command_before() {
+        assert_eq!(split_commands("grab all except cat, lever"), vec!["grab all except cat, lever"]);
+        assert_eq!(split_commands("grab all except cat, lever, go north"), vec!["grab all except cat, lever", "go north"]);
+    }
+
+    #[test]
+    fn periods_inside_words_dont_split() {
+        assert_eq!(split_commands("script log.txt"), vec!["script log.txt"]);
+        assert_eq!(split_commands("script log.txt. look"), vec!["script log.txt", "look"]);
+    }
+
+    #[test]
+    fn then_splits_like_a_period() {
+        assert_eq!(split_commands("go north then grab shovel"), vec!["go north", "grab shovel"]);
+        assert_eq!(split_commands("go north, then grab shovel"), vec!["go north", "grab shovel"]);
+    }
+}
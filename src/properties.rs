@@ -0,0 +1,292 @@
+/*
+The verbs that work the same on every item: open, close, lock, unlock, light
+and break. Rather than each item handling them in a closure, an item says which
+properties it has (see Properties in lib.rs) and these change them:
+
+    open door       opens it, unless it's locked
+    unlock door     needs one of the door's keys in the bag
+    light torch     as long as there's fuel left
+    break vase      for good; something with keys needs one of them as a tool
+
+A lockable door is then just an item with open and locked properties and a
+list of keys, plus a can_move that looks at whether it's open. Handlers in
+levels.rs can read and change the properties too, through the flags.
+*/
+use std::fmt;
+use Game;
+use Item;
+use Properties;
+use nouns;
+use nouns::Resolved;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Action {
+    Open,
+    Close,
+    Lock,
+    Unlock,
+    Light,
+    Break,
+}
+
+// what the properties are called, in messages and wherever they're asked for by name
+pub const NAMES: [&str; 6] = ["open", "locked", "lit", "broken", "raised", "count"];
+
+// one property of an item, with its value
+#[derive(Clone, Copy, PartialEq)]
+pub enum Property {
+    Open(bool),
+    Locked(bool),
+    Lit(bool),
+    Broken(bool),
+    Raised(bool),
+    Count(u32),
+}
+
+impl fmt::Display for Property {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Property::Open(open) => write!(f, "{}", if open { "open" } else { "closed" }),
+            Property::Locked(locked) => write!(f, "{}", if locked { "locked" } else { "unlocked" }),
+            Property::Lit(lit) => write!(f, "{}", if lit { "lit" } else { "out" }),
+            Property::Broken(broken) => write!(f, "{}", if broken { "broken" } else { "mended" }),
+            Property::Raised(raised) => write!(f, "{}", if raised { "raised" } else { "lowered" }),
+            Property::Count(count) => write!(f, "count {}", count),
+        }
+    }
+}
+
+impl Property {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Property::Open(_) => "open",
+            Property::Locked(_) => "locked",
+            Property::Lit(_) => "lit",
+            Property::Broken(_) => "broken",
+            Property::Raised(_) => "raised",
+            Property::Count(_) => "count",
+        }
+    }
+}
+
+// properties, with the one called name put back the way it is in original
+pub fn restore(properties: Properties, original: &Properties, name: &str) -> Properties {
+    match name {
+        "open" => Properties { open: original.open, ..properties },
+        "locked" => Properties { locked: original.locked, ..properties },
+        "lit" => Properties { lit: original.lit, ..properties },
+        "broken" => Properties { broken: original.broken, ..properties },
+        "raised" => Properties { raised: original.raised, ..properties },
+        _ => Properties { count: original.count, ..properties },
+    }
+}
+
+// properties, with the one called name set to value; None if there's no
+// property called that or the item doesn't have it. Counts aren't true or false,
+// so they can't be set this way.
+pub fn set(mut properties: Properties, name: &str, value: bool) -> Option<Properties> {
+    let property = match name {
+        "open" => &mut properties.open,
+        "locked" => &mut properties.locked,
+        "lit" => &mut properties.lit,
+        "broken" => &mut properties.broken,
+        "raised" => &mut properties.raised,
+        _ => return None,
+    };
+    *property.as_mut()? = value;
+    Some(properties)
+}
+
+// the properties that are different in after, with their new values
+pub fn changes(before: &Properties, after: &Properties) -> Vec<Property> {
+    let mut changes = Vec::new();
+    if before.open != after.open {
+        changes.extend(after.open.map(Property::Open));
+    }
+    if before.locked != after.locked {
+        changes.extend(after.locked.map(Property::Locked));
+    }
+    if before.lit != after.lit {
+        changes.extend(after.lit.map(Property::Lit));
+    }
+    if before.broken != after.broken {
+        changes.extend(after.broken.map(Property::Broken));
+    }
+    if before.raised != after.raised {
+        changes.extend(after.raised.map(Property::Raised));
+    }
+    if before.count != after.count {
+        changes.extend(after.count.map(Property::Count));
+    }
+    changes
+}
+
+// whether doing action to item takes one of its keys. Breaking only does for
+// things that list some, which are the tools it takes; a vase can still be
+// broken with bare hands.
+pub fn needs_key(item: &Item, action: Action) -> bool {
+    match action {
+        Action::Lock | Action::Unlock => true,
+        Action::Break => !item.keys.is_empty(),
+        _ => false,
+    }
+}
+
+// what action does to an item with these properties, or the ID of the message
+// that says why it can't be done. has_key is whether one of the item's keys is
+// in the bag, or no key is needed (see needs_key). Whether a light has any fuel left isn't up to the properties, so
+// lighting one still has to check that.
+pub fn apply(action: Action, properties: Properties, has_key: bool) -> Result<Properties, &'static str> {
+    match action {
+        Action::Open => match properties.open {
+            None => Err("open.cannot"),
+            Some(true) => Err("open.already"),
+            Some(false) if properties.locked == Some(true) => Err("open.locked"),
+            Some(false) => Ok(Properties { open: Some(true), ..properties }),
+        },
+        Action::Close => match properties.open {
+            None => Err("close.cannot"),
+            Some(false) => Err("close.already"),
+            Some(true) if properties.broken == Some(true) => Err("close.broken"),
+            Some(true) => Ok(Properties { open: Some(false), ..properties }),
+        },
+        Action::Lock => match properties.locked {
+            None => Err("lock.no_lock"),
+            Some(true) => Err("lock.already"),
+            Some(false) if properties.open == Some(true) => Err("lock.open"),
+            Some(false) if !has_key => Err("lock.no_key"),
+            Some(false) => Ok(Properties { locked: Some(true), ..properties }),
+        },
+        Action::Unlock => match properties.locked {
+            None => Err("lock.no_lock"),
+            Some(false) => Err("unlock.already"),
+            Some(true) if !has_key => Err("lock.no_key"),
+            Some(true) => Ok(Properties { locked: Some(false), ..properties }),
+        },
+        Action::Light => match properties.lit {
+            None => Err("light.cannot"),
+            Some(true) => Err("light.already"),
+            Some(false) if properties.broken == Some(true) => Err("light.broken"),
+            Some(false) => Ok(Properties { lit: Some(true), ..properties }),
+        },
+        Action::Break => match properties.broken {
+            None => Err("break.cannot"),
+            Some(true) => Err("break.already"),
+            Some(false) if !has_key => Err("break.no_tool"),
+            // a broken light doesn't burn any more
+            Some(false) => Ok(Properties { broken: Some(true), lit: properties.lit.map(|_| false), ..properties }),
+        },
+    }
+}
+
+impl Game {
+    // an action on something in the bag or in the room
+    pub fn handle(&mut self, action: Action, item_name: &str) -> bool {
+        let id = {
+            let in_reach: Vec<_> = self.bag().into_iter().chain(self.room_items()).collect();
+            match nouns::resolve(&in_reach, item_name) {
+                Resolved::Found(i) => in_reach[i].id,
+                Resolved::NotFound => {
+                    sayln!("{}", tr!("use.not_here", item_name));
+                    return false;
+                },
+                Resolved::Cancelled => return false,
+            }
+        };
+        self.state.last_item = Some(id);
+        self.act(action, id)
+    }
+
+    pub fn act(&mut self, action: Action, id: &'static str) -> bool {
+        let item = self.world.item(id);
+        let properties = self.state.flags.get_properties(id).cloned().unwrap_or(Properties::NONE);
+        // the first of the item's keys that the player has on them
+        let key = item.keys.iter().find(|key| self.state.inventory.contains(key)).map(|key| &self.world.item(key).name);
+
+        let changed = match apply(action, properties, key.is_some() || !needs_key(item, action)) {
+            Ok(changed) => changed,
+            Err(message) => {
+                sayln!("{}", tr!(message, item.name));
+                return false;
+            },
+        };
+        match action {
+            Action::Open => sayln!("{}", tr!("open.done", item.name)),
+            Action::Close => sayln!("{}", tr!("close.done", item.name)),
+            Action::Lock => sayln!("{}", tr!("lock.done", item.name, key.cloned().unwrap_or_default())),
+            Action::Unlock => sayln!("{}", tr!("unlock.done", item.name, key.cloned().unwrap_or_default())),
+            // there's the fuel to think of too
+            Action::Light => {
+                if !self.light(id) {
+                    return false;
+                }
+                if self.world.room(self.state.current_room).dark {
+                    self.look();
+                }
+                return true;
+            },
+            Action::Break => match key {
+                Some(tool) => sayln!("{}", tr!("break.done_with", item.name, tool)),
+                None => sayln!("{}", tr!("break.done", item.name)),
+            },
+        }
+        self.state.flags.update_properties(id, changed);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOOR: Properties = Properties { open: Some(false), locked: Some(true), ..Properties::NONE };
+    const TORCH: Properties = Properties { lit: Some(false), broken: Some(false), ..Properties::NONE };
+
+    #[test]
+    fn unlocking_needs_a_key() {
+        assert!(apply(Action::Unlock, DOOR, false) == Err("lock.no_key"));
+        assert!(apply(Action::Unlock, DOOR, true) == Ok(Properties { locked: Some(false), ..DOOR }));
+    }
+
+    #[test]
+    fn a_locked_door_doesnt_open() {
+        assert!(apply(Action::Open, DOOR, true) == Err("open.locked"));
+        let unlocked = Properties { locked: Some(false), ..DOOR };
+        assert!(apply(Action::Open, unlocked, false) == Ok(Properties { open: Some(true), ..unlocked }));
+    }
+
+    #[test]
+    fn an_open_door_doesnt_lock() {
+        let open = Properties { open: Some(true), locked: Some(false), ..DOOR };
+        assert!(apply(Action::Lock, open, true) == Err("lock.open"));
+        assert!(apply(Action::Close, open, false) == Ok(Properties { open: Some(false), ..open }));
+    }
+
+    #[test]
+    fn doing_it_twice() {
+        assert!(apply(Action::Close, DOOR, false) == Err("close.already"));
+        assert!(apply(Action::Unlock, Properties { locked: Some(false), ..DOOR }, true) == Err("unlock.already"));
+        assert!(apply(Action::Light, Properties { lit: Some(true), ..TORCH }, false) == Err("light.already"));
+    }
+
+    #[test]
+    fn breaking_a_light_puts_it_out_for_good() {
+        let broken = apply(Action::Break, Properties { lit: Some(true), ..TORCH }, true);
+        assert!(broken == Ok(Properties { lit: Some(false), broken: Some(true), ..TORCH }));
+        assert!(apply(Action::Light, Properties { broken: Some(true), ..TORCH }, false) == Err("light.broken"));
+        assert!(apply(Action::Break, Properties { broken: Some(true), ..TORCH }, true) == Err("break.already"));
+    }
+
+    #[test]
+    fn breaking_can_take_a_tool() {
+        assert!(apply(Action::Break, TORCH, false) == Err("break.no_tool"));
+    }
+
+    #[test]
+    fn properties_an_item_doesnt_have() {
+        assert!(apply(Action::Open, TORCH, false) == Err("open.cannot"));
+        assert!(apply(Action::Lock, TORCH, true) == Err("lock.no_lock"));
+        assert!(apply(Action::Light, DOOR, false) == Err("light.cannot"));
+        assert!(apply(Action::Break, DOOR, false) == Err("break.cannot"));
+    }
+}
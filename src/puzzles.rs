@@ -0,0 +1,399 @@
+/*
+Charts the chain of puzzles in the world, as a Graphviz graph: which steps
+(pulling the lever, smashing the glass door...) have to happen before which.
+
+    adventure --puzzles > puzzles.dot && dot -Tpng puzzles.dot -o puzzles.png
+
+The chart comes from playing the world forward rather than from anything written
+down. Starting from the flags of a new game, everything the player could do in
+the rooms they can reach is tried out (on a copy of the flags, with the output
+muted). Whatever changes a flag or opens an exit becomes a step, and its changes
+are kept, which may open up more rooms and more steps; this goes on until
+nothing new happens. A step needs an earlier one if undoing the flag that the
+earlier step set changes what this one does. Everything in a room also needs
+whatever opened the way there (and, in a dark room, a light), and using an item
+needs whatever made the item available. An arrow that skips over a step the
+designer expected to matter is usually a shortcut worth knowing about.
+
+Once a step has set a flag, nothing else can set it again, so anything that did
+nothing is tried once more on the flags as they were before each step. If it
+makes changes that have all been made since, it's another way of doing the same
+thing and becomes a step too. A step that needs such a change gets an arrow from
+every step that makes it; any one of them will do.
+*/
+use std::collections::{HashMap, HashSet, VecDeque};
+use console;
+use dot::quote;
+use Direction;
+use Flags;
+use Item;
+use Properties;
+use Target;
+use properties::{apply, changes, needs_key, restore, Action, NAMES};
+use world::World;
+
+const DIRECTIONS: [Direction; 4] = [Direction::North, Direction::South, Direction::East, Direction::West];
+
+// what an action changed
+#[derive(Default, PartialEq)]
+struct Effects {
+    // flag, value it was changed to
+    flags: Vec<(&'static str, bool)>,
+    // item, its properties before and after
+    items: Vec<(&'static str, Properties, Properties)>,
+}
+
+impl Effects {
+    fn is_empty(&self) -> bool {
+        self.flags.is_empty() && self.items.is_empty()
+    }
+
+    // what it changed, for the label of a step
+    fn describe(&self) -> String {
+        let mut described: Vec<String> = self.flags.iter()
+            .map(|&(flag, value)| format!("{} {}", if value { "sets" } else { "clears" }, flag))
+            .collect();
+        for &(item, ref before, ref after) in &self.items {
+            described.extend(changes(before, after).iter().map(|change| format!("{} {}", item, change)));
+        }
+        described.join(", ")
+    }
+}
+
+struct Step {
+    label: String,
+    // earlier steps this one can't happen without, and the flag that links them if any
+    needs: Vec<(usize, Option<&'static str>)>,
+    wins: bool,
+}
+
+struct Chart<'a> {
+    world: &'a World,
+    // the flags once every step found so far has been done
+    state: Flags,
+    steps: Vec<Step>,
+    // the flags as they were just before each step
+    before: Vec<Flags>,
+    // the steps that change each flag
+    setters: HashMap<&'static str, Vec<usize>>,
+    // and each property of each item (item, property name)
+    item_setters: HashMap<(&'static str, &'static str), Vec<usize>>,
+}
+
+// what running action does to the flags and the items
+fn effects(flags: &mut Flags, action: &dyn Fn(&mut Flags)) -> Effects {
+    let before = flags.fresh();
+    action(flags);
+    // both maps are sorted already
+    Effects {
+        flags: flags.flag_map.iter()
+            .filter(|&(name, value)| before.flag_map.get(name) != Some(value))
+            .map(|(&name, &value)| (name, value))
+            .collect(),
+        items: flags.properties.iter()
+            .filter_map(|(&item, &properties)| match before.properties.get(item) {
+                Some(&was) if was != properties => Some((item, was, properties)),
+                _ => None,
+            })
+            .collect(),
+    }
+}
+
+impl<'a> Chart<'a> {
+    // a copy of the current flags that hasn't been used yet
+    fn fresh(&self) -> Flags {
+        self.state.fresh()
+    }
+
+    // the steps behind the flags that make run() come out the way it does on base
+    fn needs_for<T: PartialEq>(&self, base: &Flags, run: &dyn Fn(&mut Flags) -> T) -> Vec<(usize, Option<&'static str>)> {
+        let mut flags = base.fresh();
+        let outcome = run(&mut flags);
+        let mut consulted: Vec<&'static str> = flags.used().into_iter().collect();
+        consulted.sort();
+
+        let mut needs = Vec::new();
+        for flag in consulted {
+            let setters = match self.setters.get(flag) {
+                Some(setters) => setters,
+                // never changed, so nothing to wait for
+                None => continue,
+            };
+            let mut undone = base.fresh();
+            undone.update_key(flag, self.world.flags.get_key(flag) == Some(&true));
+            if run(&mut undone) != outcome {
+                needs.extend(setters.iter().map(|&setter| (setter, Some(flag))));
+            }
+        }
+
+        // the same for the items whose properties it looked at, labelled with the item
+        let mut consulted_items: Vec<&'static str> = flags.used_items().into_iter().collect();
+        consulted_items.sort();
+        for item in consulted_items {
+            let (now, original) = match (base.properties.get(item), self.world.flags.properties.get(item)) {
+                (Some(&now), Some(original)) => (now, original),
+                _ => continue,
+            };
+            for &name in &NAMES {
+                let setters = match self.item_setters.get(&(item, name)) {
+                    Some(setters) => setters,
+                    None => continue,
+                };
+                let mut undone = base.fresh();
+                undone.update_properties(item, restore(now, original, name));
+                if run(&mut undone) != outcome {
+                    needs.extend(setters.iter().map(|&setter| (setter, Some(item))));
+                }
+            }
+        }
+        needs
+    }
+
+    // adds a step, unless it's already there; returns its index either way
+    fn add(&mut self, label: String, mut needs: Vec<(usize, Option<&'static str>)>, effects: Effects) -> usize {
+        if let Some(i) = self.steps.iter().position(|step| step.label == label) {
+            return i;
+        }
+
+        let mut seen = HashSet::new();
+        needs.retain(|&(step, _)| seen.insert(step));
+        let i = self.steps.len();
+        self.before.push(self.fresh());
+        for &(flag, value) in &effects.flags {
+            self.state.update_key(flag, value);
+            self.setters.entry(flag).or_default().push(i);
+        }
+        for &(item, ref before, ref after) in &effects.items {
+            for change in changes(before, after) {
+                self.item_setters.entry((item, change.name())).or_default().push(i);
+                let now = self.state.properties[item];
+                self.state.update_properties(item, restore(now, after, change.name()));
+            }
+        }
+        let wins = effects.flags.contains(&("gameWon", true));
+        self.steps.push(Step { label, needs, wins });
+        i
+    }
+
+    // whether everything in effects has been done already
+    fn is_done(&self, effects: &Effects) -> bool {
+        effects.flags.iter().all(|&(flag, value)| self.state.flag_map.get(flag) == Some(&value)) &&
+            effects.items.iter().all(|&(item, ref before, ref after)| {
+                let now = self.state.properties[item];
+                changes(before, after).iter().all(|change| restore(now, after, change.name()) == now)
+            })
+    }
+
+    // tries out action; if it changes any flags that becomes a step. If it
+    // doesn't, it may still be another way of doing what an earlier step did.
+    fn try_action(&mut self, what: String, action: &dyn Fn(&mut Flags), also_needs: &[Option<usize>]) -> Option<usize> {
+        let mut base = self.fresh();
+        let mut changed = effects(&mut base.fresh(), action);
+        if changed.is_empty() {
+            let earlier = self.before.iter()
+                .map(|before| (before, effects(&mut before.fresh(), action)))
+                .find(|(_, changed)| !changed.is_empty() && self.is_done(changed));
+            match earlier {
+                Some((before, earlier_changed)) => {
+                    base = before.fresh();
+                    changed = earlier_changed;
+                },
+                None => return None,
+            }
+        }
+
+        let label = format!("{}\n({})", what, changed.describe());
+        let mut needs = self.needs_for(&base, &|flags: &mut Flags| effects(flags, action));
+        needs.extend(also_needs.iter().filter_map(|&need| need.map(|step| (step, None))));
+        Some(self.add(label, needs, changed))
+    }
+
+    // every room that can be reached right now, along with the step that last
+    // opened the way there (None for rooms that were open from the start).
+    // Exits that are open now but weren't at the start become steps.
+    fn explore(&mut self) -> HashMap<&'static str, Option<usize>> {
+        let world = self.world;
+        let mut entries = HashMap::new();
+        let mut to_visit = VecDeque::new();
+        entries.insert(world.start_room, None);
+        to_visit.push_back(world.start_room);
+
+        while let Some(id) = to_visit.pop_front() {
+            let room = world.room(id);
+            for direction in &DIRECTIONS {
+                let to = match room.connections.get(*direction) {
+                    Some(to) => to,
+                    None => continue,
+                };
+                if entries.contains_key(to) || !(room.can_move)(&self.state, direction) {
+                    continue;
+                }
+
+                let mut entry = entries[id];
+                if !(room.can_move)(&world.flags, direction) {
+                    let mut needs = self.needs_for(&self.state, &|flags: &mut Flags| (room.can_move)(flags, direction));
+                    needs.extend(entry.map(|step| (step, None)));
+                    let label = format!("go {} from {}", direction.name(), room.name);
+                    entry = Some(self.add(label, needs, Effects::default()));
+                }
+                entries.insert(to, entry);
+                to_visit.push_back(to);
+            }
+        }
+
+        entries
+    }
+}
+
+// the puzzle chart for world, ready to be fed to dot
+pub fn to_dot(world: &World) -> String {
+    let mut chart = Chart { world, state: world.flags.fresh(), steps: Vec::new(), before: Vec::new(), setters: HashMap::new(), item_setters: HashMap::new() };
+    console::muted(|| find_steps(&mut chart));
+
+    let mut dot = String::from("digraph puzzles {\n");
+    dot.push_str("    node [shape=box];\n");
+    for (i, step) in chart.steps.iter().enumerate() {
+        let shape = if step.wins { ", shape=doubleoctagon" } else { "" };
+        dot.push_str(&format!("    step{} [label={}{}];\n", i, quote(&step.label), shape));
+    }
+    dot.push('\n');
+    for (i, step) in chart.steps.iter().enumerate() {
+        for &(need, flag) in &step.needs {
+            match flag {
+                Some(flag) => dot.push_str(&format!("    step{} -> step{} [label={}];\n", need, i, quote(flag))),
+                None => dot.push_str(&format!("    step{} -> step{};\n", need, i)),
+            }
+        }
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+fn find_steps(chart: &mut Chart) {
+    let world = chart.world;
+    // items the player can get their hands on, and the step that made them available
+    let mut sources: HashMap<&'static str, Option<usize>> = HashMap::new();
+    // the first step that grabbing each item made
+    let mut grabs: HashMap<&'static str, usize> = HashMap::new();
+
+    loop {
+        let found = (chart.steps.len(), sources.len());
+        let entries = chart.explore();
+
+        // everything lying around in the rooms that can be reached
+        let mut in_reach: Vec<(&Item, Option<usize>)> = Vec::new();
+        for room in &world.rooms {
+            let mut entry = match entries.get(room.id) {
+                Some(&entry) => entry,
+                None => continue,
+            };
+            if room.dark {
+                // nothing in there can be seen without a light
+                let light = world.items().into_iter()
+                    .filter(|item| item.light.is_some())
+                    .find_map(|item| sources.get(item.id).cloned());
+                let light = match light {
+                    Some(light) => light,
+                    None => continue,
+                };
+                if entry.is_some() || light.is_some() {
+                    let needs = entry.into_iter().chain(light).map(|step| (step, None)).collect();
+                    entry = Some(chart.add(format!("bring a light into {}", room.name), needs, Effects::default()));
+                }
+            }
+            in_reach.extend(room.items.iter().map(|item| (item, entry)));
+        }
+
+        // grabbing puts a grabbable item in the bag unless its on_grab refuses.
+        // If the grab did something, it's a step and the item comes from there
+        for &(item, entry) in &in_reach {
+            if let Some(grab) = chart.try_action(format!("grab {}", item.name), &|flags: &mut Flags| { (item.on_grab)(flags); }, &[entry]) {
+                grabs.entry(item.id).or_insert(grab);
+            }
+            // refused for now; it may not be once more steps are in
+            if !item.is_grabbable || sources.contains_key(item.id) || !(item.on_grab)(&mut chart.fresh()) {
+                continue;
+            }
+            let source = if let Some(&grab) = grabs.get(item.id) {
+                Some(grab)
+            }
+            else {
+                // nothing changes, but whatever on_grab looked at to let it be taken is still needed
+                let mut needs = chart.needs_for(&chart.state, &|flags: &mut Flags| (item.on_grab)(flags));
+                if needs.is_empty() {
+                    entry
+                }
+                else {
+                    needs.extend(entry.map(|step| (step, None)));
+                    Some(chart.add(format!("grab {}", item.name), needs, Effects::default()))
+                }
+            };
+            sources.insert(item.id, source);
+        }
+
+        for recipe in &world.recipes {
+            let (a, b) = recipe.ingredients;
+            if sources.contains_key(recipe.result.id) {
+                continue;
+            }
+            if let (Some(&from_a), Some(&from_b)) = (sources.get(a), sources.get(b)) {
+                let needs = from_a.into_iter().chain(from_b).map(|step| (step, None)).collect();
+                let step = chart.add(format!("combine {} and {}", a, b), needs, Effects::default());
+                sources.insert(recipe.result.id, Some(step));
+            }
+        }
+        // what the recipes make can be tried out like any other item
+        for recipe in &world.recipes {
+            if let Some(&source) = sources.get(recipe.result.id) {
+                in_reach.push((&recipe.result, source));
+            }
+        }
+
+        // then every item in the bag on everything in reach, and on every room
+        for &(item, _) in &in_reach {
+            let on_use = match (sources.get(item.id), item.on_use.as_ref()) {
+                (Some(_), Some(on_use)) => on_use,
+                _ => continue,
+            };
+            let source = sources[item.id];
+            for &(target, target_source) in &in_reach {
+                if target.id == item.id {
+                    continue;
+                }
+                let action = |flags: &mut Flags| { on_use(flags, &Target::Item(target)); };
+                chart.try_action(format!("use {} on {}", item.name, target.name), &action, &[source, target_source]);
+            }
+            for room in &world.rooms {
+                if let Some(&entry) = entries.get(room.id) {
+                    let action = |flags: &mut Flags| { on_use(flags, &Target::Room(room.id)); };
+                    chart.try_action(format!("use {} in {}", item.name, room.name), &action, &[source, entry]);
+                }
+            }
+        }
+
+        // and the verbs that work on anything with the right properties. Closing
+        // and locking only ever take things away, so they're left out.
+        for &(item, source) in &in_reach {
+            if !chart.state.properties.contains_key(item.id) {
+                continue;
+            }
+            // any key the player can get their hands on will do
+            let key = item.keys.iter().find_map(|key| sources.get(key).cloned());
+            for &(action, verb) in &[(Action::Open, "open"), (Action::Unlock, "unlock"), (Action::Light, "light"), (Action::Break, "break")] {
+                let needs_key = needs_key(item, action);
+                let act = |flags: &mut Flags| {
+                    let properties = flags.get_properties(item.id).cloned().unwrap_or(Properties::NONE);
+                    if let Ok(changed) = apply(action, properties, key.is_some() || !needs_key) {
+                        flags.update_properties(item.id, changed);
+                    }
+                };
+                let key_source = if needs_key { key.and_then(|key| key) } else { None };
+                chart.try_action(format!("{} {}", verb, item.name), &act, &[source, key_source]);
+            }
+        }
+
+        if (chart.steps.len(), sources.len()) == found {
+            break;
+        }
+    }
+}
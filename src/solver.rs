@@ -0,0 +1,180 @@
+/*
+Finds the shortest list of commands that wins the game, by breadth-first search
+over everything the player could type. The commands are run through the real
+parser and Game::process_command(), so the walkthrough is exactly what a player
+would have to type; it can be fed straight back in with --replay.
+
+    adventure --solve > walkthrough.txt
+
+Instead of building a new world for every state, a single game is rewound to
+the GameState being expanded before each command is tried.
+
+States are compared without the order things were picked up or put down in,
+how much fuel the lights have left, or the parts of the GameState that don't
+change what can happen next (the map, the hints and score, what 'it' means),
+or every turn would make a new one and the search would never get anywhere. Lights in a level should burn long
+enough for that not to matter; a level that needs the player to save fuel may
+be reported unwinnable when it isn't.
+
+Dropping things is only tried when the bag is too full for something that's
+still to be found, since making room is all it's good for. Even so, the search
+gives up after MAX_STATES states rather than run for ever.
+*/
+use std::collections::{HashSet, VecDeque};
+use console;
+use i18n;
+use nouns;
+use parser;
+use state::GameState;
+use Game;
+use Item;
+use Properties;
+
+// the word the game itself uses for a verb, in the current language
+fn word(list: &'static str) -> &'static str {
+    i18n::words(list)[0]
+}
+
+// how many states the search goes through before giving up
+const MAX_STATES: usize = 200_000;
+
+pub enum Solution {
+    Won(Vec<String>),
+    // nothing that can be typed wins the game; how many states were tried
+    Unwinnable(usize),
+    // there were too many states to go through them all
+    GaveUp(usize),
+}
+
+impl Game {
+    fn restore(&mut self, state: &GameState) {
+        self.state = state.clone();
+    }
+
+    // everything worth typing in the current state
+    fn possible_commands(&self) -> Vec<String> {
+        let mut commands = Vec::new();
+        let room = self.world.room(self.state.current_room);
+        let room_items = self.room_items();
+        let in_bag = self.bag();
+        // something that isn't in the bag yet wouldn't fit
+        let must_make_room = self.world.items().iter()
+            .any(|item| item.is_grabbable && !self.state.inventory.contains(&item.id) && !self.has_room_for(item));
+
+        for (direction, _) in room.connections.exits() {
+            commands.push(format!("{} {}", word("words.go"), direction.local_name()));
+        }
+        // names that would make the game ask "which do you mean?" are left out
+        for item in &room_items {
+            if nouns::is_unambiguous(&room_items, &item.name) {
+                commands.push(format!("{} {}", word("words.grab"), item.name));
+            }
+        }
+        for item in &in_bag {
+            if !nouns::is_unambiguous(&in_bag, &item.name) {
+                continue;
+            }
+            commands.push(format!("{} {}", word("words.use"), item.name));
+            if must_make_room {
+                commands.push(format!("{} {}", word("words.drop"), item.name));
+            }
+
+            let others: Vec<&Item> = in_bag.iter().filter(|other| other.id != item.id).chain(room_items.iter()).cloned().collect();
+            for other in &others {
+                if nouns::is_unambiguous(&others, &other.name) {
+                    commands.push(format!("{} {} {} {}", word("words.use"), item.name, word("words.on"), other.name));
+                }
+            }
+        }
+        // open, unlock and so on, only for things that have properties at all
+        let in_reach: Vec<&Item> = in_bag.iter().chain(room_items.iter()).cloned().collect();
+        for item in &in_reach {
+            if item.properties == Properties::NONE || !nouns::is_unambiguous(&in_reach, &item.name) {
+                continue;
+            }
+            for verb in &["words.open", "words.close", "words.lock", "words.unlock", "words.light", "words.break"] {
+                commands.push(format!("{} {}", word(verb), item.name));
+            }
+        }
+
+        commands
+    }
+}
+
+// the shortest list of commands that wins the game, starting from where game is now
+pub fn solve(game: &mut Game) -> Solution {
+    console::muted(|| search(game))
+}
+
+// what a state is compared by; see the top of the file
+fn normalised(state: &GameState) -> GameState {
+    let mut state = state.clone();
+    state.inventory.sort();
+    for items in state.locations.values_mut() {
+        items.sort();
+    }
+    state.fuel.clear();
+    state.visited.clear();
+    state.clues_given.clear();
+    state.score = 0;
+    state.last_item = None;
+    state
+}
+
+// the cell in Flags that records what was looked at isn't part of its hash or
+// equality, so GameStates are fine as keys
+#[allow(clippy::mutable_key_type)]
+fn search(game: &mut Game) -> Solution {
+    let start = game.state.clone();
+    // every state found so far, with the state it came from and the command that got there
+    let mut states: Vec<(GameState, Option<(usize, String)>)> = vec![(start.clone(), None)];
+    let mut seen = HashSet::new();
+    seen.insert(normalised(&start));
+    let mut to_expand = VecDeque::new();
+    to_expand.push_back(0);
+
+    while let Some(i) = to_expand.pop_front() {
+        let state = states[i].0.clone();
+        game.restore(&state);
+        let commands = game.possible_commands();
+
+        for text in commands {
+            game.restore(&state);
+            let command = match parser::parse(&text) {
+                Ok(command) => command,
+                Err(_) => continue,
+            };
+            game.process_command(command);
+
+            if game.state.is_won() {
+                let mut walkthrough = vec![text];
+                let mut at = i;
+                while let Some((from, ref step)) = states[at].1 {
+                    walkthrough.push(step.clone());
+                    at = from;
+                }
+                walkthrough.reverse();
+                return Solution::Won(walkthrough);
+            }
+            // some other ending; nothing more can happen from here
+            if !game.state.is_running() {
+                continue;
+            }
+
+            // nothing happened, which is most of the time
+            if game.state == state {
+                continue;
+            }
+            let next = game.state.clone();
+            if seen.insert(normalised(&next)) {
+                if states.len() >= MAX_STATES {
+                    return Solution::GaveUp(states.len());
+                }
+                states.push((next, Some((i, text))));
+                to_expand.push_back(states.len() - 1);
+            }
+        }
+    }
+
+    Solution::Unwinnable(states.len())
+}
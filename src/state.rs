@@ -0,0 +1,172 @@
+/*
+Everything about a game in progress that changes as it's played: where the
+player is, what they're carrying, where every item lies, how much the lights
+have left to burn, the flags, which hold the items' properties too, and the
+rest of what a saved game would need (the rooms on the map, the hints given,
+the score and what 'it' means). The world
+(rooms, items and what they do) never changes once it's built, so it's kept
+apart and only referred to by ID from here. That makes a GameState cheap to
+copy, compare and write out.
+*/
+use std::collections::{BTreeMap, BTreeSet};
+use serde::{Serialize, Serializer};
+use serde::ser::SerializeStruct;
+use Flags;
+use Properties;
+use MAX_SCORE;
+use world::World;
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct GameState {
+    pub current_room: &'static str,
+    // item IDs, in the order they were picked up
+    pub inventory: Vec<&'static str>,
+    // room ID -> the IDs of the items lying in it
+    pub locations: BTreeMap<&'static str, Vec<&'static str>>,
+    // light ID -> how many more turns it can burn for
+    pub fuel: BTreeMap<&'static str, u32>,
+    // items the player put down somewhere, which the room descriptions don't mention
+    pub dropped: BTreeSet<&'static str>,
+    pub flags: Flags,
+    // rooms that go on the map
+    pub visited: BTreeSet<&'static str>,
+    // hint index -> how many of its clues were shown
+    pub clues_given: BTreeMap<usize, usize>,
+    pub score: u32,
+    // the ID of what the player last grabbed or used, for 'it' and 'them'
+    pub last_item: Option<&'static str>,
+}
+
+// the properties live in the flags, where the handlers can get at them, but are
+// written out as a field of their own so the flags stay a plain name -> bool map
+impl Serialize for GameState {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("GameState", 11)?;
+        state.serialize_field("current_room", &self.current_room)?;
+        state.serialize_field("inventory", &self.inventory)?;
+        state.serialize_field("locations", &self.locations)?;
+        state.serialize_field("fuel", &self.fuel)?;
+        state.serialize_field("dropped", &self.dropped)?;
+        state.serialize_field("flags", &self.flags)?;
+        state.serialize_field("properties", &self.flags.properties)?;
+        state.serialize_field("visited", &self.visited)?;
+        state.serialize_field("clues_given", &self.clues_given)?;
+        state.serialize_field("score", &self.score)?;
+        state.serialize_field("last_item", &self.last_item)?;
+        state.end()
+    }
+}
+
+impl GameState {
+    // a new game of world
+    pub fn new(world: &World) -> GameState {
+        GameState {
+            current_room: world.start_room,
+            inventory: Vec::new(),
+            locations: world.rooms.iter().map(|room| (room.id, room.items.iter().map(|item| item.id).collect())).collect(),
+            fuel: world.items().iter().filter_map(|item| item.light.as_ref().map(|light| (item.id, light.fuel))).collect(),
+            dropped: BTreeSet::new(),
+            flags: world.flags.clone(),
+            visited: Some(world.start_room).into_iter().collect(),
+            clues_given: BTreeMap::new(),
+            score: MAX_SCORE,
+            last_item: None,
+        }
+    }
+
+    pub fn items_in(&self, room_id: &str) -> &[&'static str] {
+        self.locations.get(room_id).map_or(&[], |items| items.as_slice())
+    }
+
+    // moves an item from the current room into the bag
+    pub fn take(&mut self, item_id: &'static str) {
+        if let Some(items) = self.locations.get_mut(self.current_room) {
+            items.retain(|&id| id != item_id);
+        }
+        self.dropped.remove(item_id);
+        self.inventory.push(item_id);
+    }
+
+    // moves an item from the bag into the current room
+    pub fn put_down(&mut self, item_id: &'static str) {
+        self.inventory.retain(|&id| id != item_id);
+        self.locations.entry(self.current_room).or_default().push(item_id);
+        self.dropped.insert(item_id);
+    }
+
+    // takes an item out of the game, because it was used up. Of a pile, only
+    // one goes.
+    pub fn use_up(&mut self, item_id: &'static str) {
+        if let Some(&properties) = self.flags.get_properties(item_id) {
+            if let Some(count) = properties.count.filter(|&count| count > 1) {
+                self.flags.update_properties(item_id, Properties { count: Some(count - 1), ..properties });
+                return;
+            }
+        }
+        self.inventory.retain(|&id| id != item_id);
+    }
+
+    // whether it's a light that's burning right now
+    pub fn is_burning(&self, item_id: &'static str) -> bool {
+        self.flags.get_properties(item_id).and_then(|properties| properties.lit) == Some(true)
+    }
+
+    pub fn set_lit(&mut self, item_id: &'static str, lit: bool) {
+        if let Some(&properties) = self.flags.get_properties(item_id) {
+            self.flags.update_properties(item_id, Properties { lit: Some(lit), ..properties });
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.flags.get_key("isGameRunning") == Some(&true)
+    }
+
+    pub fn is_won(&self) -> bool {
+        self.flags.get_key("gameWon") == Some(&true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use levels;
+    use serde_json;
+
+    #[test]
+    fn serializes_a_new_game() {
+        let world = levels::create_world().ok().expect("the world should build");
+        let state = GameState::new(&world);
+        let json = serde_json::to_value(&state).unwrap();
+
+        assert_eq!(json["current_room"], "cell");
+        assert_eq!(json["inventory"], serde_json::json!([]));
+        assert_eq!(json["locations"]["greenhouse"], serde_json::json!(["shovel", "glass_door", "twine"]));
+        assert_eq!(json["fuel"]["torch"], 150);
+        assert_eq!(json["visited"], serde_json::json!(["cell"]));
+        assert_eq!(json["score"], MAX_SCORE);
+        assert_eq!(json["last_item"], serde_json::Value::Null);
+        // the flags are a plain map, with the properties beside them
+        assert_eq!(json["flags"]["pickedUpCat"], false);
+        assert!(json["flags"].get("glass_door").is_none());
+        assert_eq!(json["properties"]["glass_door"]["broken"], false);
+        assert_eq!(json["properties"]["shack_door"]["locked"], true);
+    }
+
+    #[test]
+    fn serializes_what_changed() {
+        let world = levels::create_world().ok().expect("the world should build");
+        let mut state = GameState::new(&world);
+        state.take("lever");
+        state.flags.update_key("initialSwitchPulled", true);
+        state.clues_given.insert(0, 2);
+        state.score -= 5;
+        state.last_item = Some("lever");
+        let json = serde_json::to_value(&state).unwrap();
+
+        assert_eq!(json["inventory"], serde_json::json!(["lever"]));
+        assert_eq!(json["flags"]["initialSwitchPulled"], true);
+        assert_eq!(json["clues_given"]["0"], 2);
+        assert_eq!(json["score"], MAX_SCORE - 5);
+        assert_eq!(json["last_item"], "lever");
+    }
+}
@@ -0,0 +1,373 @@
+/*
+Checks a world for mistakes before anybody plays it. Errors (an exit leading to
+a room that doesn't exist, two things sharing an ID...) stop the game from
+starting. Warnings are things that are legal but probably not what the designer
+meant, like a room nobody can get to; those are only shown by --check.
+
+Only the quick checks of IDs and exits are done when the game starts; going
+through the handlers and the translations takes a while, so that waits for
+--check.
+
+Which flags the handlers use can't be read out of the closures, so they are
+found by calling every handler on a copy of the flags (with the output muted)
+and writing down which flags they asked about. The same goes for the item IDs
+that use handlers check their targets against (see Target::is). Each handler is
+called once with the flags as they start out, once with every flag flipped,
+once with each single flag flipped and once with every door, lock and light the
+other way round, which is enough to reach every branch in levels.rs.
+
+Translations are checked too: every message English has should be in the other
+languages, or players of those get English in the middle of their game.
+*/
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use console;
+use i18n;
+use Direction;
+use Flags;
+use Hint;
+use Item;
+use Recipe;
+use Room;
+use Target;
+use World;
+
+pub enum Problem {
+    DuplicateRoom(&'static str),
+    DuplicateItem(&'static str),
+    // room, direction, the room it points to
+    UnknownExit(&'static str, &'static str, &'static str),
+    UnknownStartRoom(&'static str),
+    UnknownIngredient(&'static str),
+    // the item with the lock, the key
+    UnknownKey(&'static str, &'static str),
+    // an item whose properties a handler looked at
+    UnknownPropertyItem(&'static str),
+    // an item a use handler checks its target against
+    UnknownTarget(&'static str),
+    UndeclaredFlag(&'static str),
+    // the index of the hint
+    HintWithoutClues(usize),
+    // the warnings
+    OneWayExit(&'static str, &'static str, &'static str),
+    DuplicateItemName(&'static str, String),
+    UnusableUseHandler(&'static str),
+    UnreachableRoom(&'static str),
+    UnusedFlag(&'static str),
+    DarkWithoutLight(&'static str),
+    LightWithoutLit(&'static str),
+    // item, its weight, how much the bag holds
+    TooHeavy(&'static str, u32, u32),
+    // language, message ID
+    MissingText(&'static str, &'static str),
+}
+
+impl Problem {
+    pub fn is_error(&self) -> bool {
+        match *self {
+            Problem::DuplicateRoom(_) | Problem::DuplicateItem(_) | Problem::UnknownExit(..) |
+            Problem::UnknownStartRoom(_) | Problem::UnknownIngredient(_) | Problem::UnknownKey(..) |
+            Problem::UnknownPropertyItem(_) | Problem::UnknownTarget(_) | Problem::UndeclaredFlag(_) | Problem::HintWithoutClues(_) => true,
+            Problem::OneWayExit(..) | Problem::DuplicateItemName(..) | Problem::UnusableUseHandler(_) |
+            Problem::UnreachableRoom(_) | Problem::UnusedFlag(_) | Problem::DarkWithoutLight(_) | Problem::LightWithoutLit(_) | Problem::TooHeavy(..) |
+            Problem::MissingText(..) => false,
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_error() {
+            write!(f, "error: ")?;
+        }
+        else {
+            write!(f, "warning: ")?;
+        }
+
+        match *self {
+            Problem::DuplicateRoom(id) => write!(f, "there is more than one room with the ID '{}'", id),
+            Problem::DuplicateItem(id) => write!(f, "there is more than one item with the ID '{}'", id),
+            Problem::UnknownExit(room, direction, to) => write!(f, "the {} exit of '{}' leads to '{}', which isn't a room", direction, room, to),
+            Problem::UnknownStartRoom(id) => write!(f, "the game starts in '{}', which isn't a room", id),
+            Problem::UnknownIngredient(id) => write!(f, "a recipe needs '{}', which isn't an item", id),
+            Problem::UnknownKey(item, key) => write!(f, "'{}' can be unlocked with '{}', which isn't an item", item, key),
+            Problem::UnknownPropertyItem(id) => write!(f, "the properties of '{}' are used, but it isn't an item", id),
+            Problem::UnknownTarget(id) => write!(f, "a use handler checks for '{}', which isn't an item", id),
+            Problem::UndeclaredFlag(flag) => write!(f, "the flag '{}' is used but never declared in create_flags()", flag),
+            Problem::HintWithoutClues(hint) => write!(f, "hint number {} has no clues to give", hint + 1),
+            Problem::OneWayExit(room, direction, to) => write!(f, "the {} exit of '{}' leads to '{}', but there is no way back", direction, room, to),
+            Problem::DuplicateItemName(room, ref name) => write!(f, "'{}' has more than one item called '{}'", room, name),
+            Problem::UnusableUseHandler(item) => write!(f, "'{}' can't be picked up, so its on_use handler can never run", item),
+            Problem::UnreachableRoom(room) => write!(f, "'{}' can't be reached from the starting room", room),
+            Problem::UnusedFlag(flag) => write!(f, "the flag '{}' is declared but never used", flag),
+            Problem::DarkWithoutLight(room) => write!(f, "'{}' is dark, but there's nothing in the world to light it", room),
+            Problem::LightWithoutLit(item) => write!(f, "'{}' gives light, but has no lit property to say whether it's burning", item),
+            Problem::TooHeavy(item, weight, capacity) => write!(f, "'{}' weighs {}, but the bag only holds {}, so it can never be carried", item, weight, capacity),
+            Problem::MissingText(language, id) => write!(f, "lang/{}.txt has no '{}'", language, id),
+        }
+    }
+}
+
+const DIRECTIONS: [Direction; 4] = [Direction::North, Direction::South, Direction::East, Direction::West];
+
+// the quick checks, done every time the world is built
+pub fn check(rooms: &[Room], recipes: &[Recipe], hints: &[Hint], start_room: &'static str, capacity: u32) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    check_ids(rooms, recipes, start_room, &mut problems);
+    check_exits(rooms, start_room, &mut problems);
+    check_items(rooms, &mut problems);
+    check_lights(rooms, recipes, &mut problems);
+    check_weights(rooms, recipes, capacity, &mut problems);
+    for (i, hint) in hints.iter().enumerate() {
+        if hint.clues.is_empty() {
+            problems.push(Problem::HintWithoutClues(i));
+        }
+    }
+
+    problems.sort_by_key(|problem| !problem.is_error());
+    problems
+}
+
+// the slow ones, for --check only: every handler gets called over and over,
+// and every translation is gone through
+pub fn check_handlers(world: &World) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    check_flags(&world.rooms, &world.recipes, &world.hints, &world.flags, &mut problems);
+    for &language in i18n::language_codes().iter().skip(1) {
+        problems.extend(i18n::missing(language).into_iter().map(|id| Problem::MissingText(language, id)));
+    }
+
+    problems.sort_by_key(|problem| !problem.is_error());
+    problems
+}
+
+fn check_ids(rooms: &[Room], recipes: &[Recipe], start_room: &'static str, problems: &mut Vec<Problem>) {
+    let mut room_ids = HashSet::new();
+    let mut item_ids = HashSet::new();
+    for room in rooms {
+        if !room_ids.insert(room.id) {
+            problems.push(Problem::DuplicateRoom(room.id));
+        }
+        for item in &room.items {
+            if !item_ids.insert(item.id) {
+                problems.push(Problem::DuplicateItem(item.id));
+            }
+        }
+    }
+
+    // what recipes make is looked up by ID like everything else
+    for recipe in recipes {
+        if !item_ids.insert(recipe.result.id) {
+            problems.push(Problem::DuplicateItem(recipe.result.id));
+        }
+    }
+
+    let items = rooms.iter().flat_map(|room| room.items.iter()).chain(recipes.iter().map(|recipe| &recipe.result));
+    for item in items {
+        for key in item.keys.iter().filter(|key| !item_ids.contains(*key)) {
+            problems.push(Problem::UnknownKey(item.id, key));
+        }
+    }
+
+    if !room_ids.contains(start_room) {
+        problems.push(Problem::UnknownStartRoom(start_room));
+    }
+    for recipe in recipes {
+        let (a, b) = recipe.ingredients;
+        for ingredient in &[a, b] {
+            if !item_ids.contains(ingredient) {
+                problems.push(Problem::UnknownIngredient(ingredient));
+            }
+        }
+    }
+}
+
+fn check_exits(rooms: &[Room], start_room: &'static str, problems: &mut Vec<Problem>) {
+    let by_id: HashMap<&'static str, &Room> = rooms.iter().map(|room| (room.id, room)).collect();
+
+    for room in rooms {
+        for (direction, to) in room.connections.exits() {
+            match by_id.get(to) {
+                None => problems.push(Problem::UnknownExit(room.id, direction.name(), to)),
+                Some(other) => {
+                    // any exit back will do; paths don't have to be straight
+                    if !other.connections.exits().iter().any(|&(_, back)| back == room.id) {
+                        problems.push(Problem::OneWayExit(room.id, direction.name(), to));
+                    }
+                },
+            }
+        }
+    }
+
+    // walk every exit from the start and see which rooms never come up
+    if !by_id.contains_key(start_room) {
+        return;
+    }
+    let mut reached = HashSet::new();
+    let mut to_visit = VecDeque::new();
+    reached.insert(start_room);
+    to_visit.push_back(start_room);
+    while let Some(id) = to_visit.pop_front() {
+        for (_, to) in by_id[id].connections.exits() {
+            if by_id.contains_key(to) && reached.insert(to) {
+                to_visit.push_back(to);
+            }
+        }
+    }
+    for room in rooms {
+        if !reached.contains(room.id) {
+            problems.push(Problem::UnreachableRoom(room.id));
+        }
+    }
+}
+
+fn check_items(rooms: &[Room], problems: &mut Vec<Problem>) {
+    for room in rooms {
+        let mut names = HashSet::new();
+        for item in &room.items {
+            if !names.insert(item.name.to_lowercase()) {
+                problems.push(Problem::DuplicateItemName(room.id, item.name.clone()));
+            }
+            // only things in the bag get used
+            if !item.is_grabbable && item.on_use.is_some() {
+                problems.push(Problem::UnusableUseHandler(item.id));
+            }
+        }
+    }
+}
+
+// a dark room nothing can light is one nobody will ever see inside
+fn check_lights(rooms: &[Room], recipes: &[Recipe], problems: &mut Vec<Problem>) {
+    let lights: Vec<&Item> = rooms.iter()
+        .flat_map(|room| room.items.iter())
+        .chain(recipes.iter().map(|recipe| &recipe.result))
+        .filter(|item| item.light.is_some())
+        .collect();
+    for light in lights.iter().filter(|light| light.properties.lit.is_none()) {
+        problems.push(Problem::LightWithoutLit(light.id));
+    }
+    if !lights.is_empty() {
+        return;
+    }
+    for room in rooms.iter().filter(|room| room.dark) {
+        problems.push(Problem::DarkWithoutLight(room.id));
+    }
+}
+
+fn check_weights(rooms: &[Room], recipes: &[Recipe], capacity: u32, problems: &mut Vec<Problem>) {
+    let items = rooms.iter()
+        .flat_map(|room| room.items.iter())
+        .chain(recipes.iter().map(|recipe| &recipe.result));
+    for item in items.filter(|item| item.is_grabbable && item.weight > capacity) {
+        problems.push(Problem::TooHeavy(item.id, item.weight, capacity));
+    }
+}
+
+fn check_flags(rooms: &[Room], recipes: &[Recipe], hints: &[Hint], flags: &Flags, problems: &mut Vec<Problem>) {
+    let declared = flags.names();
+    let (used, used_items, used_targets) = console::muted(|| probe_handlers(rooms, recipes, hints, flags));
+
+    let mut unknown_items: Vec<&'static str> = used_items.into_iter().filter(|item| !flags.properties.contains_key(item)).collect();
+    unknown_items.sort();
+    for item in unknown_items {
+        problems.push(Problem::UnknownPropertyItem(item));
+    }
+    // every item has properties, even if they're all None
+    let mut unknown_targets: Vec<&'static str> = used_targets.into_iter().filter(|item| !flags.properties.contains_key(item)).collect();
+    unknown_targets.sort();
+    for item in unknown_targets {
+        problems.push(Problem::UnknownTarget(item));
+    }
+
+    let mut undeclared: Vec<&'static str> = used.iter().filter(|flag| !declared.contains(*flag)).cloned().collect();
+    undeclared.sort();
+    for flag in undeclared {
+        problems.push(Problem::UndeclaredFlag(flag));
+    }
+
+    let mut unused: Vec<&'static str> = declared.iter().filter(|&&flag| !used.contains(flag) && !Flags::is_engine_flag(flag)).cloned().collect();
+    unused.sort();
+    for flag in unused {
+        problems.push(Problem::UnusedFlag(flag));
+    }
+}
+
+// calls every handler in the world with a few different sets of flags, and
+// returns every flag they looked at or changed, every item whose properties
+// they did, and every item a use target was checked against
+fn probe_handlers(rooms: &[Room], recipes: &[Recipe], hints: &[Hint], flags: &Flags) -> (HashSet<&'static str>, HashSet<&'static str>, HashSet<&'static str>) {
+    let names = flags.names();
+
+    // with every open door shut, every lock undone and so on too
+    let mut properties_flipped = flags.fresh();
+    for properties in properties_flipped.properties.values_mut() {
+        properties.open = properties.open.map(|open| !open);
+        properties.locked = properties.locked.map(|locked| !locked);
+        properties.lit = properties.lit.map(|lit| !lit);
+        properties.broken = properties.broken.map(|broken| !broken);
+        properties.raised = properties.raised.map(|raised| !raised);
+    }
+
+    let mut states = vec![flags.clone(), properties_flipped];
+    let mut all_flipped = flags.clone();
+    for name in &names {
+        let mut one_flipped = flags.clone();
+        one_flipped.update_key(name, flags.get_key(name) != Some(&true));
+        states.push(one_flipped);
+        all_flipped.update_key(name, flags.get_key(name) != Some(&true));
+    }
+    states.push(all_flipped);
+    // flipping them counted as using them; start over with only the values
+    let states: Vec<Flags> = states.iter().map(|state| state.fresh()).collect();
+
+    let mut used = HashSet::new();
+    let mut used_items = HashSet::new();
+    let mut used_targets = HashSet::new();
+    for state in &states {
+        for recipe in recipes {
+            let mut craft_state = state.clone();
+            (recipe.craft)(&mut craft_state);
+            used.extend(craft_state.used());
+            used_items.extend(craft_state.used_items());
+        }
+    }
+    let items: Vec<&Item> = rooms.iter().flat_map(|room| room.items.iter())
+        .chain(recipes.iter().map(|recipe| &recipe.result))
+        .collect();
+
+    for state in &states {
+        for room in rooms {
+            (room.get_description)(state);
+            for direction in &DIRECTIONS {
+                (room.can_move)(state, direction);
+            }
+            for hint in hints {
+                (hint.applies)(state, room.id);
+            }
+        }
+        used.extend(state.used());
+        used_items.extend(state.used_items());
+
+        // these change the flags, so each gets its own copy
+        for item in &items {
+            let mut grab_state = state.clone();
+            (item.on_grab)(&mut grab_state);
+            used.extend(grab_state.used());
+            used_items.extend(grab_state.used_items());
+
+            if let Some(ref on_use) = item.on_use {
+                let targets = items.iter().map(|&target| Target::Item(target))
+                    .chain(rooms.iter().map(|room| Target::Room(room.id)));
+                for target in targets {
+                    let mut use_state = state.clone();
+                    on_use(&mut use_state, &target);
+                    used.extend(use_state.used());
+                    used_items.extend(use_state.used_items());
+                    used_targets.extend(use_state.used_targets());
+                }
+            }
+        }
+    }
+
+    (used, used_items, used_targets)
+}
@@ -0,0 +1,204 @@
+/*
+Wizard mode, for testing a level without playing all of it. Started with
+--wizard, it adds a few commands that cheat:
+
+    teleport ROOM       go straight to a room, by ID or name
+    set FLAG VALUE      change a flag (true/false, on/off, yes/no)
+    set ITEM PROPERTY VALUE
+                        change one of an item's properties, like
+                        'set glass_door broken true'; ITEM is the item's ID
+    flags               list every flag and its value, and the items' properties
+    spawn ITEM          put any item in the bag, wherever it was
+    rooms               list the rooms
+    items               list the items and where they are
+
+So trying out the ending is: spawn torch, teleport shack interior, grab head
+(the shack is dark, so the torch has to come along).
+Nothing checks that what the wizard does makes sense; setting a flag can leave
+the game in a state no player could ever get to.
+*/
+use parser::ParseError;
+use properties;
+use properties::changes;
+use Command;
+use events::Event;
+use Game;
+use Properties;
+
+const VERBS: [&str; 6] = ["teleport", "set", "flags", "spawn", "rooms", "items"];
+
+pub enum Spell {
+    Teleport(String),
+    Set(String, bool),
+    // item ID, property name, value
+    SetProperty(String, String, bool),
+    Flags,
+    Spawn(String),
+    Rooms,
+    Items,
+}
+
+fn truth(word: &str) -> Option<bool> {
+    match word {
+        "true"|"on"|"yes"|"1" => Some(true),
+        "false"|"off"|"no"|"0" => Some(false),
+        _ => None,
+    }
+}
+
+pub fn is_verb(word: &str) -> bool {
+    VERBS.contains(&word.to_lowercase().as_str())
+}
+
+// None if it isn't a wizard command at all, so the normal parser can have a go
+pub fn parse(raw_value: &str) -> Option<Result<Command, ParseError>> {
+    let value = raw_value.to_lowercase();
+    let container: Vec<&str> = value.split_whitespace().collect();
+    let rest = container.get(1 ..).unwrap_or(&[]).join(" ");
+
+    let spell = match container.first() {
+        Some(&"teleport") if rest.is_empty() => Err(ParseError::MissingItem("teleport".to_string())),
+        Some(&"teleport") => Ok(Spell::Teleport(rest)),
+        Some(&"spawn") if rest.is_empty() => Err(ParseError::MissingItem("spawn".to_string())),
+        Some(&"spawn") => Ok(Spell::Spawn(rest)),
+        Some(&"set") => {
+            // syntax: set FLAG VALUE, or set ITEM PROPERTY VALUE
+            match container.len() {
+                1 => Err(ParseError::MissingItem("set".to_string())),
+                2 => Err(ParseError::MissingValue(container[1].to_string())),
+                3 => match truth(container[2]) {
+                    Some(value) => Ok(Spell::Set(container[1].to_string(), value)),
+                    // an item's property, still missing its value
+                    None if properties::NAMES.contains(&container[2]) => Err(ParseError::MissingValue(container[1 ..].join(" "))),
+                    None => Err(ParseError::Unexpected(container[.. 2].join(" "), container[2].to_string())),
+                },
+                4 => match truth(container[3]) {
+                    Some(value) => Ok(Spell::SetProperty(container[1].to_string(), container[2].to_string(), value)),
+                    None => Err(ParseError::Unexpected(container[.. 3].join(" "), container[3].to_string())),
+                },
+                _ => Err(ParseError::Unexpected(container[.. 4].join(" "), container[4 ..].join(" "))),
+            }
+        },
+        Some(&"flags") => Ok(Spell::Flags),
+        Some(&"rooms") => Ok(Spell::Rooms),
+        Some(&"items") => Ok(Spell::Items),
+        _ => return None,
+    };
+    Some(spell.map(Command::Wizard))
+}
+
+impl Game {
+    pub fn cast(&mut self, spell: Spell) -> bool {
+        match spell {
+            Spell::Teleport(name) => self.teleport(&name),
+            Spell::Set(name, value) => self.set_flag(&name, value),
+            Spell::SetProperty(item, name, value) => self.set_property(&item, &name, value),
+            Spell::Flags => {
+                for (name, value) in &self.state.flags.flag_map {
+                    sayln!("{} = {}", name, value);
+                }
+                for (item, properties) in self.state.flags.properties.iter().filter(|&(_, properties)| *properties != Properties::NONE) {
+                    let properties: Vec<String> = changes(&Properties::NONE, properties).iter().map(|property| property.to_string()).collect();
+                    sayln!("{}: {}", item, properties.join(", "));
+                }
+                true
+            },
+            Spell::Spawn(name) => self.spawn(&name),
+            Spell::Rooms => {
+                for room in &self.world.rooms {
+                    let here = if room.id == self.state.current_room { " (you are here)" } else { "" };
+                    sayln!("{}: {}{}", room.id, room.name, here);
+                }
+                true
+            },
+            Spell::Items => {
+                for item in self.world.items() {
+                    sayln!("{}: {} ({})", item.id, item.name, self.whereabouts(item.id));
+                }
+                true
+            },
+        }
+    }
+
+    fn teleport(&mut self, name: &str) -> bool {
+        let to = match self.world.rooms.iter().find(|room| room.id == name || room.name.to_lowercase() == name) {
+            Some(room) => room.id,
+            None => {
+                sayln!("There is no room called '{}'. Type 'rooms' to see them all.", name);
+                return false;
+            },
+        };
+
+        let from = self.state.current_room;
+        self.state.current_room = to;
+        self.state.visited.insert(to);
+        self.emit(Event::RoomEntered { from, to });
+        self.describe_room(to);
+        true
+    }
+
+    fn set_flag(&mut self, name: &str, value: bool) -> bool {
+        // update_key() wants the name the world was built with
+        let flag = match self.state.flags.names().into_iter().find(|flag| flag.to_lowercase() == name) {
+            Some(flag) => flag,
+            None => {
+                sayln!("There is no flag called '{}'. Type 'flags' to see them all.", name);
+                return false;
+            },
+        };
+        self.state.flags.update_key(flag, value);
+        sayln!("{} = {}", flag, value);
+        true
+    }
+
+    fn set_property(&mut self, item: &str, name: &str, value: bool) -> bool {
+        let (id, properties) = match self.state.flags.properties.iter().find(|&(&id, _)| id == item) {
+            Some((&id, &properties)) => (id, properties),
+            None => {
+                sayln!("There is no item with properties called '{}'. Type 'flags' to see them all.", item);
+                return false;
+            },
+        };
+        let changed = match properties::set(properties, name, value) {
+            Some(changed) => changed,
+            None => {
+                sayln!("'{}' has no property called '{}' that can be set.", id, name);
+                return false;
+            },
+        };
+        self.state.flags.update_properties(id, changed);
+        sayln!("{} {} = {}", id, name, value);
+        true
+    }
+
+    fn spawn(&mut self, name: &str) -> bool {
+        let id = match self.world.items().into_iter().find(|item| item.id == name || item.name.to_lowercase() == name) {
+            Some(item) => item.id,
+            None => {
+                sayln!("There is no item called '{}'. Type 'items' to see them all.", name);
+                return false;
+            },
+        };
+
+        // out of wherever it was, so that there's only ever one of it. Like
+        // everything else the wizard does, this doesn't care whether it fits.
+        for items in self.state.locations.values_mut() {
+            items.retain(|&other| other != id);
+        }
+        self.state.inventory.retain(|&other| other != id);
+        self.state.inventory.push(id);
+        self.emit(Event::ItemTaken(id));
+        sayln!("A {} appears in your bag.", self.world.item(id).name);
+        true
+    }
+
+    fn whereabouts(&self, id: &str) -> String {
+        if self.state.inventory.contains(&id) {
+            return "in the bag".to_string();
+        }
+        match self.state.locations.iter().find(|&(_, items)| items.contains(&id)) {
+            Some((room, _)) => format!("in {}", room),
+            None => "nowhere".to_string(),
+        }
+    }
+}
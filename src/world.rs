@@ -0,0 +1,98 @@
+/*
+The rooms, items and recipes that make up a game, looked up by their IDs. Rooms
+point at each other by ID rather than by their position in the list, so rooms
+can be added or moved around in levels.rs without renumbering anything. The
+IDs are run through the validator once, when the world is built, so a typo in a
+Connection is found at startup instead of when the player walks through that
+door. Nothing in here changes during a game; see state.rs for what does.
+*/
+use std::collections::HashMap;
+use Flags;
+use Hint;
+use Item;
+use Room;
+use Recipe;
+use validate;
+use validate::Problem;
+
+// where the definition of an item is kept
+enum Definition {
+    // room, and index into its items
+    InRoom(usize, usize),
+    // made by a recipe
+    Crafted(usize),
+}
+
+pub struct World {
+    pub rooms: Vec<Room>,
+    pub recipes: Vec<Recipe>,
+    // in the order the puzzles are solved; the first one that applies is given
+    pub hints: Vec<Hint>,
+    // the flags as they are when a new game starts
+    pub flags: Flags,
+    pub start_room: &'static str,
+    // how much the bag holds; the weights of the items in it can't add up to more
+    pub capacity: u32,
+    // things the validator didn't like but that won't break the game
+    pub warnings: Vec<Problem>,
+    room_index: HashMap<&'static str, usize>,
+    item_index: HashMap<&'static str, Definition>,
+}
+
+impl World {
+    // fails with everything the validator found if any of it is an error
+    pub fn new(rooms: Vec<Room>, recipes: Vec<Recipe>, hints: Vec<Hint>, mut flags: Flags, start_room: &'static str, capacity: u32) -> Result<World, Vec<Problem>> {
+        // the items' properties go in with the flags, where the handlers can see them
+        flags.properties = rooms.iter()
+            .flat_map(|room| room.items.iter())
+            .chain(recipes.iter().map(|recipe| &recipe.result))
+            .map(|item| (item.id, item.properties))
+            .collect();
+        let problems = validate::check(&rooms, &recipes, &hints, start_room, capacity);
+        if problems.iter().any(|problem| problem.is_error()) {
+            return Err(problems);
+        }
+
+        let room_index = rooms.iter().enumerate().map(|(i, room)| (room.id, i)).collect();
+        let mut item_index = HashMap::new();
+        for (i, room) in rooms.iter().enumerate() {
+            for (j, item) in room.items.iter().enumerate() {
+                item_index.insert(item.id, Definition::InRoom(i, j));
+            }
+        }
+        for (i, recipe) in recipes.iter().enumerate() {
+            item_index.insert(recipe.result.id, Definition::Crafted(i));
+        }
+
+        Ok(World { rooms, recipes, hints, flags, start_room, capacity, warnings: problems, room_index, item_index })
+    }
+
+    // room IDs are checked in new(), so any ID taken from the world is safe here
+    pub fn room(&self, id: &str) -> &Room {
+        &self.rooms[self.room_index[id]]
+    }
+
+    // same for items
+    pub fn item(&self, id: &str) -> &Item {
+        match self.item_index[id] {
+            Definition::InRoom(room, i) => &self.rooms[room].items[i],
+            Definition::Crafted(recipe) => &self.recipes[recipe].result,
+        }
+    }
+
+    // everything in the world, including what the recipes make
+    pub fn items(&self) -> Vec<&Item> {
+        self.rooms.iter()
+            .flat_map(|room| room.items.iter())
+            .chain(self.recipes.iter().map(|recipe| &recipe.result))
+            .collect()
+    }
+
+    // the recipe that combines these two items, in either order
+    pub fn recipe_for(&self, first: &str, second: &str) -> Option<&Recipe> {
+        self.recipes.iter().find(|recipe| {
+            let (a, b) = recipe.ingredients;
+            (a == first && b == second) || (a == second && b == first)
+        })
+    }
+}